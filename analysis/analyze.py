@@ -1,7 +1,7 @@
 """Analyze prompting-style experiment results.
 
 Usage:
-    cd analysis && uv run python analyze.py [--results PATH]
+    cd analysis && uv run python analyze.py [--experiment ID ...] [--results PATH ...]
 """
 
 from __future__ import annotations
@@ -57,6 +57,25 @@ def cliffs_delta(a: np.ndarray, b: np.ndarray) -> tuple[float, str]:
 # Data loading
 # ---------------------------------------------------------------------------
 
+RESULTS_ROOT = Path(__file__).resolve().parent.parent / "results"
+
+
+def latest_experiment(results_root: Path) -> str | None:
+    """Return the ID of the most recently created experiment that has been evaluated."""
+    candidates: list[tuple[str, str]] = []
+    if not results_root.is_dir():
+        return None
+    for exp_dir in results_root.iterdir():
+        if not (exp_dir / "eval.json").is_file():
+            continue
+        created_at = ""
+        manifest = exp_dir / "manifest.json"
+        if manifest.is_file():
+            created_at = json.loads(manifest.read_text()).get("created_at", "")
+        candidates.append((created_at, exp_dir.name))
+    return max(candidates)[1] if candidates else None
+
+
 def load_results(path: Path) -> pd.DataFrame:
     """Load results.json into a DataFrame, or exit gracefully."""
     if not path.exists():
@@ -77,6 +96,31 @@ def load_results(path: Path) -> pd.DataFrame:
     return df
 
 
+def load_experiments(paths: list[Path]) -> pd.DataFrame:
+    """Load and concatenate several eval outputs, or exit gracefully."""
+    frames = [load_results(p) for p in paths]
+    df = pd.concat(frames, ignore_index=True)
+    return df
+
+
+def load_adaptive(paths: list[Path]) -> pd.DataFrame:
+    """Load the runner's adaptive.json stopping decisions next to each eval output.
+
+    Returns an empty DataFrame when no experiment used adaptive sampling.
+    """
+    frames: list[pd.DataFrame] = []
+    for path in paths:
+        adaptive = path.parent / "adaptive.json"
+        if not adaptive.is_file():
+            continue
+        groups = json.loads(adaptive.read_text()).get("groups", [])
+        if groups:
+            frame = pd.DataFrame(groups)
+            frame["experiment_id"] = path.parent.name
+            frames.append(frame)
+    return pd.concat(frames, ignore_index=True) if frames else pd.DataFrame()
+
+
 # ---------------------------------------------------------------------------
 # Aggregate metrics
 # ---------------------------------------------------------------------------
@@ -128,6 +172,30 @@ def compute_tier_aggregates(df: pd.DataFrame) -> pd.DataFrame:
     return pd.DataFrame(rows)
 
 
+def summarize_adaptive(df: pd.DataFrame, decisions: pd.DataFrame) -> dict:
+    """Account for adaptive sampling.
+
+    Under adaptive sampling, noisy task×style groups receive more runs than
+    stable ones, so pooling runs over-weights noisy groups. The cell-weighted
+    pass rate averages per-group means instead, giving every task equal weight.
+    """
+    if decisions.empty:
+        return {}
+    by_style: list[dict] = []
+    for style, g in decisions.groupby("style"):
+        group_means = df.loc[df["style"] == style].groupby("task_id")["pass"].mean()
+        by_style.append({
+            "style": style,
+            "groups": len(g),
+            "converged": int((g["decision"] == "converged").sum()),
+            "capped": int((g["decision"] == "cap").sum()),
+            "interrupted": int((g["decision"] == "interrupted").sum()),
+            "mean_runs": float(g["runs"].mean()),
+            "cell_weighted_pass_rate": float(group_means.mean()) if len(group_means) else np.nan,
+        })
+    return {"by_style": by_style}
+
+
 # ---------------------------------------------------------------------------
 # Failure-mode diagnostics
 # ---------------------------------------------------------------------------
@@ -221,9 +289,134 @@ def diagnose_failures(df: pd.DataFrame) -> dict:
         worst = task_stats.head(10).reset_index()
         diag["worst_tasks"] = worst.to_dict(orient="records")
 
+    # --- 5. Outcome classes by style ----------------------------------------
+    if "outcome" in df.columns and "style" in df.columns:
+        table = df.groupby(["outcome", "style"]).size().unstack(fill_value=0)
+        diag["outcomes_by_style"] = {
+            "styles": list(table.columns),
+            "rows": table.reset_index().to_dict(orient="records"),
+        }
+
+    # --- 6. Compiler error codes by style ----------------------------------
+    # How often each rustc error code occurs per style, e.g. to compare
+    # borrow-checker errors (E0502/E0499) across prompting styles.
+    if "error_codes" in df.columns and "style" in df.columns:
+        codes = df[["style", "error_codes"]].explode("error_codes").dropna()
+        if not codes.empty:
+            table = codes.groupby(["error_codes", "style"]).size().unstack(fill_value=0)
+            table["total"] = table.sum(axis=1)
+            table = table.sort_values("total", ascending=False).head(15)
+            diag["error_codes_by_style"] = {
+                "styles": [c for c in table.columns if c != "total"],
+                "rows": table.reset_index().rename(columns={"error_codes": "code"}).to_dict(orient="records"),
+                "responses": df.groupby("style").size().to_dict(),
+            }
+
+    # --- 7. Code extraction strategies ------------------------------------
+    # How the code was pulled out of each response (fenced block, merged
+    # blocks, prose trimmed, ...), with the pass rate each strategy yields.
+    if "extraction" in df.columns and "style" in df.columns:
+        table = df.groupby(["extraction", "style"]).size().unstack(fill_value=0)
+        rates = df.groupby("extraction")["pass"].mean()
+        rows = table.reset_index().to_dict(orient="records")
+        for r in rows:
+            r["pass_rate"] = float(rates[r["extraction"]])
+        diag["extraction_by_style"] = {"styles": list(table.columns), "rows": rows}
+
+    # --- 8. Response kinds and prose volume ---------------------------------
+    # Whether the model answered with code at all, and how much it wrote
+    # beside it despite the code-only system prompt.
+    if "response_kind" in df.columns and "style" in df.columns:
+        table = df.groupby(["response_kind", "style"]).size().unstack(fill_value=0)
+        styles = list(table.columns)
+        result: dict = {"styles": styles, "rows": table.reset_index().to_dict(orient="records")}
+        if "prose_lines" in df.columns:
+            result["mean_prose_lines"] = df.groupby("style")["prose_lines"].mean().to_dict()
+            groups = [df.loc[df["style"] == st, "prose_lines"].values for st in styles]
+            result["prose_kruskal_p"] = np.nan
+            if len(groups) >= 2 and df["prose_lines"].nunique() > 1:
+                result["prose_kruskal_p"] = float(stats.kruskal(*groups).pvalue)
+        diag["response_kinds_by_style"] = result
+
     return diag
 
 
+# ---------------------------------------------------------------------------
+# Failure clustering
+# ---------------------------------------------------------------------------
+
+# A cluster is flagged as a likely specification problem when it spans every
+# style and more than this share of the models.
+SPEC_PROBLEM_MODEL_SHARE = 0.5
+
+
+def failure_signature(failure: dict) -> str:
+    """Normalize a failure from eval's `failures` list so that runs failing
+    the same way compare equal: the panic message (minus locations and
+    addresses) plus the assertion values."""
+    parts = [failure.get("message") or "", failure.get("left") or "", failure.get("right") or ""]
+    text = "\n".join(parts)
+    text = re.sub(r"0x[0-9a-fA-F]+", "0x_", text)
+    text = re.sub(r"\S+\.rs:\d+:\d+", "<loc>", text)
+    text = re.sub(r"[ \t]+", " ", text)
+    return text.strip()
+
+
+def cluster_failures(df: pd.DataFrame) -> pd.DataFrame:
+    """Group test failures by task, test and normalized panic message.
+
+    Each row is one cluster: how many runs, styles and models it covers and
+    whether it looks like an ambiguous task rather than model error.
+    """
+    if "failures" not in df.columns:
+        return pd.DataFrame()
+    model_col = "model" if "model" in df.columns else None
+    rows: list[dict] = []
+    for rec in df.to_dict(orient="records"):
+        for failure in rec.get("failures") or []:
+            rows.append({
+                "task_id": rec["task_id"],
+                "test": failure.get("test"),
+                "signature": failure_signature(failure),
+                "style": rec["style"],
+                "model": rec[model_col] if model_col else "",
+                "message": failure.get("message"),
+                "left": failure.get("left"),
+                "right": failure.get("right"),
+            })
+    if not rows:
+        return pd.DataFrame()
+
+    fails = pd.DataFrame(rows)
+    # Styles each task was run under, so a task left out of some styles can
+    # still be flagged.
+    task_styles = df.groupby("task_id")["style"].nunique()
+    all_models = df[model_col].nunique() if model_col else 1
+    clusters = (
+        fails.groupby(["task_id", "test", "signature"])
+        .agg(
+            runs=("style", "size"),
+            styles=("style", lambda x: sorted(set(x))),
+            models=("model", lambda x: sorted(set(x))),
+            message=("message", "first"),
+            left=("left", "first"),
+            right=("right", "first"),
+        )
+        .reset_index()
+    )
+    # `first` yields NaN for all-missing groups; keep them as None for JSON.
+    for col in ("message", "left", "right"):
+        clusters[col] = clusters[col].astype(object).where(clusters[col].notna(), None)
+    clusters["model_share"] = clusters["models"].map(len) / all_models
+    clusters["likely_spec_problem"] = (
+        clusters["styles"].map(len) == clusters["task_id"].map(task_styles)
+    ) & (clusters["model_share"] > SPEC_PROBLEM_MODEL_SHARE)
+    clusters["task_file"] = "tasks/definitions/" + clusters["task_id"] + ".toml"
+    return clusters.sort_values(
+        ["likely_spec_problem", "runs"], ascending=[False, False]
+    ).reset_index(drop=True)
+
+
 # ---------------------------------------------------------------------------
 # Statistical tests
 # ---------------------------------------------------------------------------
@@ -264,6 +457,262 @@ def run_statistical_tests(df: pd.DataFrame, styles: list[str]) -> dict:
     }
 
 
+CLIPPY_GROUPS = ["style", "complexity", "perf", "correctness", "suspicious", "pedantic"]
+
+
+def compare_lints(df: pd.DataFrame, styles: list[str]) -> dict:
+    """Compare clippy lint density (hits per 100 LOC) across styles, per
+    lint group and in total, with a Kruskal-Wallis test for each.
+
+    Only responses that compiled and were linted are included.
+    """
+    if "clippy_ran" not in df.columns or "loc" not in df.columns:
+        return {}
+    d = df.loc[df["clippy_ran"].fillna(False).astype(bool) & (df["loc"] > 0)].copy()
+    if d.empty:
+        return {}
+    columns = [f"clippy_{g}" for g in CLIPPY_GROUPS]
+    d["clippy_total"] = d[columns].sum(axis=1)
+
+    rows: list[dict] = []
+    for name in CLIPPY_GROUPS + ["total"]:
+        density = d[f"clippy_{name}"] / d["loc"] * 100
+        groups = [density[d["style"] == s].values for s in styles]
+        row: dict = {"group": name}
+        for s, g in zip(styles, groups):
+            row[s] = float(g.mean()) if len(g) else np.nan
+        if len(groups) >= 2 and all(len(g) > 0 for g in groups) and density.nunique() > 1:
+            row["H"], row["p"] = stats.kruskal(*groups)
+        else:
+            row["H"], row["p"] = np.nan, np.nan
+        rows.append(row)
+
+    # Each response reports only its most frequent lints, so these totals
+    # undercount rare lints.
+    top = d[["style", "clippy_top"]].explode("clippy_top").dropna()
+    top_rows: list[dict] = []
+    if not top.empty:
+        top["lint"] = top["clippy_top"].map(lambda t: t["lint"])
+        top["count"] = top["clippy_top"].map(lambda t: t["count"])
+        table = top.pivot_table(index="lint", columns="style", values="count",
+                                aggfunc="sum", fill_value=0)
+        table["total"] = table.sum(axis=1)
+        table = table.sort_values("total", ascending=False).head(10)
+        top_rows = table.reset_index().to_dict(orient="records")
+
+    return {
+        "styles": styles,
+        "n": d.groupby("style").size().to_dict(),
+        "rows": rows,
+        "top": top_rows,
+    }
+
+
+def compare_formatting(df: pd.DataFrame, styles: list[str]) -> dict:
+    """Share of responses already in rustfmt style, per style, with a
+    chi-square test of formatted vs. unformatted across styles and a
+    Kruskal-Wallis test on the diff size. Responses rustfmt couldn't parse
+    are counted but left out of both tests."""
+    if "fmt_status" not in df.columns:
+        return {}
+    d = df.loc[df["fmt_status"] != "not_checked"]
+    if d.empty:
+        return {}
+
+    rows: list[dict] = []
+    for s in styles:
+        g = d.loc[d["style"] == s]
+        parsed = g.loc[g["fmt_status"] != "parse_error"]
+        rows.append({
+            "style": s,
+            "n": len(g),
+            "parse_error": int((g["fmt_status"] == "parse_error").sum()),
+            "formatted_rate": float((parsed["fmt_status"] == "formatted").mean()) if len(parsed) else np.nan,
+            "mean_diff_lines": float(parsed["fmt_diff_lines"].mean()) if len(parsed) else np.nan,
+        })
+
+    parsed = d.loc[d["fmt_status"] != "parse_error"]
+    table = pd.crosstab(parsed["style"], parsed["fmt_status"])
+    chi2_p = np.nan
+    if table.shape[0] >= 2 and table.shape[1] >= 2:
+        _, chi2_p, _, _ = stats.chi2_contingency(table)
+    groups = [parsed.loc[parsed["style"] == s, "fmt_diff_lines"].values for s in styles]
+    kw_p = np.nan
+    if len(groups) >= 2 and all(len(g) > 0 for g in groups) and parsed["fmt_diff_lines"].nunique() > 1:
+        _, kw_p = stats.kruskal(*groups)
+
+    return {"rows": rows, "chi2_p": chi2_p, "kruskal_p": kw_p}
+
+
+def compare_placeholders(df: pd.DataFrame, styles: list[str]) -> dict:
+    """Share of responses with placeholder code (`todo!()`, elided code,
+    stubs) per style, with a chi-square test across styles, and how often
+    each placeholder kind occurs."""
+    if "placeholder_count" not in df.columns:
+        return {}
+    has = df["placeholder_count"] > 0
+    table = pd.crosstab(df["style"], has)
+    chi2_p = np.nan
+    if table.shape[0] >= 2 and table.shape[1] >= 2:
+        _, chi2_p, _, _ = stats.chi2_contingency(table)
+
+    rows: list[dict] = []
+    for s in styles:
+        g = df.loc[df["style"] == s]
+        rows.append({
+            "style": s,
+            "n": len(g),
+            "rate": float((g["placeholder_count"] > 0).mean()) if len(g) else np.nan,
+            "mean_count": float(g["placeholder_count"].mean()) if len(g) else np.nan,
+        })
+
+    kinds: list[dict] = []
+    if "placeholder_kinds" in df.columns:
+        exploded = df[["style", "placeholder_kinds"]].explode("placeholder_kinds").dropna()
+        if not exploded.empty:
+            kt = exploded.groupby(["placeholder_kinds", "style"]).size().unstack(fill_value=0)
+            kinds = kt.reset_index().rename(columns={"placeholder_kinds": "kind"}).to_dict(orient="records")
+
+    return {"rows": rows, "chi2_p": chi2_p, "kinds": kinds, "styles": styles}
+
+
+def compare_own_tests(df: pd.DataFrame, styles: list[str]) -> dict:
+    """How often each style's responses include their own unit tests or doc
+    examples (chi-square across styles), how many, and how many pass."""
+    if "wrote_tests" not in df.columns:
+        return {}
+    table = pd.crosstab(df["style"], df["wrote_tests"].astype(bool))
+    chi2_p = np.nan
+    if table.shape[0] >= 2 and table.shape[1] >= 2:
+        _, chi2_p, _, _ = stats.chi2_contingency(table)
+
+    rows: list[dict] = []
+    for s in styles:
+        g = df.loc[df["style"] == s]
+        unit = g["own_unit_tests"].sum()
+        doc = g["own_doctests"].sum()
+        rows.append({
+            "style": s,
+            "n": len(g),
+            "wrote_tests_rate": float(g["wrote_tests"].mean()) if len(g) else np.nan,
+            "unit_tests": int(unit),
+            "unit_pass_rate": float(g["own_unit_passed"].sum() / unit) if unit else np.nan,
+            "doctests": int(doc),
+            "doctest_pass_rate": float(g["own_doctests_passed"].sum() / doc) if doc else np.nan,
+        })
+    return {"rows": rows, "chi2_p": chi2_p}
+
+
+def compare_properties(df: pd.DataFrame, styles: list[str]) -> dict:
+    """Agreement with the reference solution on random inputs, for tasks with
+    a property test, per style. `overfit` counts responses that pass every
+    hand-written test yet diverge from the reference."""
+    if "property_status" not in df.columns:
+        return {}
+    ran = df.loc[df["property_status"].isin(["agreed", "diverged"])]
+    if ran.empty:
+        return {}
+    table = pd.crosstab(ran["style"], ran["property_status"])
+    chi2_p = np.nan
+    if table.shape[0] >= 2 and table.shape[1] >= 2:
+        _, chi2_p, _, _ = stats.chi2_contingency(table)
+
+    rows: list[dict] = []
+    for s in styles:
+        g = ran.loc[ran["style"] == s]
+        diverged = g["property_status"] == "diverged"
+        passed = g["tests_passed"] == g["tests_total"]
+        rows.append({
+            "style": s,
+            "n": len(g),
+            "diverged_rate": float(diverged.mean()) if len(g) else np.nan,
+            "overfit": int((diverged & passed).sum()),
+        })
+    return {"rows": rows, "chi2_p": chi2_p}
+
+
+def compare_visibility(df: pd.DataFrame, styles: list[str]) -> dict:
+    """Pass rates on visible (prompt-showable) and hidden test cases per style,
+    for tasks that have both sets, and a Kruskal-Wallis test of the gap
+    between them across styles."""
+    if "visibility_gap" not in df.columns:
+        return {}
+    split = df.loc[df["visibility_gap"].notna()]
+    if split.empty:
+        return {}
+    groups = [split.loc[split["style"] == s, "visibility_gap"].to_numpy() for s in styles]
+    groups = [g for g in groups if len(g) > 0]
+    kw_p = np.nan
+    if len(groups) >= 2 and len(np.unique(np.concatenate(groups))) > 1:
+        _, kw_p = stats.kruskal(*groups)
+
+    rows: list[dict] = []
+    for s in styles:
+        g = split.loc[split["style"] == s]
+        rows.append({
+            "style": s,
+            "n": len(g),
+            "visible_rate": float(g["visible_passed"].sum() / g["visible_total"].sum()) if len(g) else np.nan,
+            "hidden_rate": float(g["hidden_passed"].sum() / g["hidden_total"].sum()) if len(g) else np.nan,
+            "mean_gap": float(g["visibility_gap"].mean()) if len(g) else np.nan,
+        })
+    return {"rows": rows, "kw_p": kw_p}
+
+
+def compare_benchmarks(df: pd.DataFrame, styles: list[str]) -> dict:
+    """Scaling against the reference solution, for tasks with a benchmark, per
+    style: how often the response's time grows faster than the task allows,
+    and its median time relative to the reference at the largest size."""
+    if "bench_status" not in df.columns:
+        return {}
+    ran = df.loc[df["bench_status"].isin(["measured", "timeout"])]
+    if ran.empty:
+        return {}
+    table = pd.crosstab(ran["style"], ran["bench_flagged"])
+    chi2_p = np.nan
+    if table.shape[0] >= 2 and table.shape[1] >= 2:
+        _, chi2_p, _, _ = stats.chi2_contingency(table)
+
+    rows: list[dict] = []
+    for s in styles:
+        g = ran.loc[ran["style"] == s]
+        ratio = g["bench_time_ratio"].dropna()
+        rows.append({
+            "style": s,
+            "n": len(g),
+            "flagged_rate": float(g["bench_flagged"].astype(bool).mean()) if len(g) else np.nan,
+            "median_ratio": float(ratio.median()) if len(ratio) else np.nan,
+        })
+    return {"rows": rows, "chi2_p": chi2_p}
+
+
+def compare_flakiness(df: pd.DataFrame, styles: list[str]) -> dict:
+    """Flaky responses per style among those whose tests were rerun: some test
+    passed in some runs but not all. `flaky_passes` counts responses scored
+    as passing on their first run that failed in a rerun."""
+    if "reruns" not in df.columns:
+        return {}
+    rerun = df.loc[(df["reruns"] > 0) & df["compiled"]]
+    if rerun.empty:
+        return {}
+    table = pd.crosstab(rerun["style"], rerun["flaky"])
+    chi2_p = np.nan
+    if table.shape[0] >= 2 and table.shape[1] >= 2:
+        _, chi2_p, _, _ = stats.chi2_contingency(table)
+
+    rows: list[dict] = []
+    for s in styles:
+        g = rerun.loc[rerun["style"] == s]
+        flaky = g["flaky"].astype(bool)
+        rows.append({
+            "style": s,
+            "n": len(g),
+            "flaky_rate": float(flaky.mean()) if len(g) else np.nan,
+            "flaky_passes": int((flaky & (g["outcome"] == "passed")).sum()),
+        })
+    return {"rows": rows, "chi2_p": chi2_p}
+
+
 # ---------------------------------------------------------------------------
 # Markdown report
 # ---------------------------------------------------------------------------
@@ -273,12 +722,24 @@ def generate_summary(
     tier_agg: pd.DataFrame,
     test_results: dict,
     diagnostics: dict,
+    adaptive: dict,
+    lints: dict,
+    formatting: dict,
+    placeholders: dict,
+    own_tests: dict,
+    properties: dict,
+    visibility: dict,
+    benchmarks: dict,
+    flakiness: dict,
+    experiments: list[str],
     out_path: Path,
 ) -> None:
     """Write summary.md."""
     lines: list[str] = []
 
     lines.append("# Prompting-Style Analysis Summary\n")
+    if experiments:
+        lines.append(f"**Experiments:** {', '.join(experiments)}\n")
 
     # Overall metrics table
     lines.append("## Overall Metrics by Style\n")
@@ -308,6 +769,21 @@ def generate_summary(
             )
         lines.append("")
 
+    # Adaptive sampling
+    if adaptive:
+        lines.append("## Adaptive Sampling\n")
+        lines.append("Runs per task×style group were chosen adaptively, so pooled pass rates "
+                     "over-weight noisy groups. The cell-weighted pass rate gives each group "
+                     "equal weight.\n")
+        lines.append("| Style | Groups | Converged | Capped | Interrupted | Mean Runs | Cell-Weighted Pass Rate |")
+        lines.append("|-------|--------|-----------|--------|-------------|-----------|-------------------------|")
+        for r in adaptive["by_style"]:
+            lines.append(
+                f"| {r['style']} | {r['groups']} | {r['converged']} | {r['capped']} "
+                f"| {r['interrupted']} | {r['mean_runs']:.1f} | {r['cell_weighted_pass_rate']:.3f} |"
+            )
+        lines.append("")
+
     # Per-tier breakdown
     lines.append("## Per-Tier Breakdown\n")
     lines.append("| Style | Tier | n | Pass Rate | Std | 95% CI |")
@@ -319,6 +795,150 @@ def generate_summary(
         )
     lines.append("")
 
+    # Clippy lint density
+    if lints:
+        st = lints["styles"]
+        lines.append("## Idiomatic Quality (Clippy)\n")
+        lines.append(
+            "Mean clippy hits per 100 LOC among compiled responses, linted with a pinned "
+            "profile; Kruskal-Wallis across styles for each lint group. Responses linted: "
+            + ", ".join(f"{s} {lints['n'].get(s, 0)}" for s in st) + ".\n"
+        )
+        lines.append("| Group | " + " | ".join(st) + " | H | p |")
+        lines.append("|-------|" + "|".join("---" for _ in st) + "|---|---|")
+        for r in lints["rows"]:
+            lines.append(
+                f"| {r['group']} | " + " | ".join(f"{r[s]:.2f}" for s in st)
+                + f" | {r['H']:.3f} | {r['p']:.4f} |"
+            )
+        lines.append("")
+        if lints["top"]:
+            lines.append("### Most Frequent Lints\n")
+            lines.append("| Lint | " + " | ".join(st) + " | Total |")
+            lines.append("|------|" + "|".join("---" for _ in st) + "|-------|")
+            for r in lints["top"]:
+                lines.append(
+                    f"| `{r['lint']}` | " + " | ".join(str(r.get(s, 0)) for s in st)
+                    + f" | {r['total']} |"
+                )
+            lines.append("")
+
+    # rustfmt conformance
+    if formatting:
+        lines.append("## Formatting (rustfmt)\n")
+        lines.append(
+            f"Share of parseable responses that rustfmt leaves unchanged (χ² p = "
+            f"{formatting['chi2_p']:.4f}) and mean lines in rustfmt's diff (Kruskal-Wallis "
+            f"p = {formatting['kruskal_p']:.4f}).\n"
+        )
+        lines.append("| Style | n | Parse Errors | Formatted | Mean Diff Lines |")
+        lines.append("|-------|---|--------------|-----------|-----------------|")
+        for r in formatting["rows"]:
+            lines.append(
+                f"| {r['style']} | {r['n']} | {r['parse_error']} "
+                f"| {r['formatted_rate']:.3f} | {r['mean_diff_lines']:.1f} |"
+            )
+        lines.append("")
+
+    # Placeholder code
+    if placeholders:
+        st = placeholders["styles"]
+        lines.append("## Incomplete Implementations\n")
+        lines.append(
+            "Responses containing `todo!()`/`unimplemented!()`, comments in place of code, "
+            "`// TODO`, or stub functions that return a default "
+            f"(χ² across styles: p = {placeholders['chi2_p']:.4f}).\n"
+        )
+        lines.append("| Style | n | With Placeholders | Mean Placeholders |")
+        lines.append("|-------|---|-------------------|-------------------|")
+        for r in placeholders["rows"]:
+            lines.append(f"| {r['style']} | {r['n']} | {r['rate']:.3f} | {r['mean_count']:.2f} |")
+        lines.append("")
+        if placeholders["kinds"]:
+            lines.append("Responses containing each kind:\n")
+            lines.append("| Kind | " + " | ".join(st) + " |")
+            lines.append("|------|" + "|".join("---" for _ in st) + "|")
+            for r in placeholders["kinds"]:
+                lines.append(f"| {r['kind']} | " + " | ".join(str(r.get(s, 0)) for s in st) + " |")
+            lines.append("")
+
+    # Model-authored tests
+    if own_tests:
+        lines.append("## Model-Authored Tests\n")
+        lines.append(
+            "Unit tests and doc examples the responses wrote themselves, built and run apart "
+            f"from the task's tests (χ² on writing any tests: p = {own_tests['chi2_p']:.4f}).\n"
+        )
+        lines.append("| Style | n | Wrote Tests | Unit Tests | Unit Pass Rate | Doctests | Doctest Pass Rate |")
+        lines.append("|-------|---|-------------|------------|----------------|----------|-------------------|")
+        for r in own_tests["rows"]:
+            lines.append(
+                f"| {r['style']} | {r['n']} | {r['wrote_tests_rate']:.3f} | {r['unit_tests']} "
+                f"| {r['unit_pass_rate']:.3f} | {r['doctests']} | {r['doctest_pass_rate']:.3f} |"
+            )
+        lines.append("")
+
+    # Differential property tests
+    if properties:
+        lines.append("## Agreement with the Reference Solution\n")
+        lines.append(
+            "Responses to tasks with a property test, run on random inputs beside the reference "
+            f"solution (χ² on diverging: p = {properties['chi2_p']:.4f}). \"Overfit\" responses "
+            "pass every hand-written test but diverge.\n"
+        )
+        lines.append("| Style | n | Diverged | Overfit |")
+        lines.append("|-------|---|----------|---------|")
+        for r in properties["rows"]:
+            lines.append(f"| {r['style']} | {r['n']} | {r['diverged_rate']:.3f} | {r['overfit']} |")
+        lines.append("")
+
+    # Visible vs hidden test sets
+    if visibility:
+        lines.append("## Visible vs Hidden Tests\n")
+        lines.append(
+            "Pass rates on the test cases prompts may show and on the held-out ones, for tasks "
+            f"with both sets (Kruskal-Wallis on the gap: p = {visibility['kw_p']:.4f}).\n"
+        )
+        lines.append("| Style | n | Visible Pass Rate | Hidden Pass Rate | Mean Gap |")
+        lines.append("|-------|---|-------------------|------------------|----------|")
+        for r in visibility["rows"]:
+            lines.append(
+                f"| {r['style']} | {r['n']} | {r['visible_rate']:.3f} | {r['hidden_rate']:.3f} "
+                f"| {r['mean_gap']:.3f} |"
+            )
+        lines.append("")
+
+    # Scaling benchmarks
+    if benchmarks:
+        lines.append("## Scaling vs the Reference Solution\n")
+        lines.append(
+            "Responses to tasks with a benchmark, timed at growing sizes beside the reference "
+            f"solution (χ² on flagged: p = {benchmarks['chi2_p']:.4f}). \"Flagged\" responses "
+            "grow faster than the task allows; the time ratio is the response's time over the "
+            "reference's at the largest size both finished.\n"
+        )
+        lines.append("| Style | n | Flagged | Median Time Ratio |")
+        lines.append("|-------|---|---------|-------------------|")
+        for r in benchmarks["rows"]:
+            lines.append(
+                f"| {r['style']} | {r['n']} | {r['flagged_rate']:.3f} | {r['median_ratio']:.2f} |"
+            )
+        lines.append("")
+
+    # Reruns of the test binaries
+    if flakiness:
+        lines.append("## Flaky Tests\n")
+        lines.append(
+            "Responses whose test binaries were rerun under varied test threads and CPU "
+            f"contention (χ² on flaky: p = {flakiness['chi2_p']:.4f}). \"Flaky passes\" were "
+            "scored as passing but failed some test in a rerun.\n"
+        )
+        lines.append("| Style | n | Flaky | Flaky Passes |")
+        lines.append("|-------|---|-------|--------------|")
+        for r in flakiness["rows"]:
+            lines.append(f"| {r['style']} | {r['n']} | {r['flaky_rate']:.3f} | {r['flaky_passes']} |")
+        lines.append("")
+
     # ── Failure-mode diagnostics ──────────────────────────────────────
     lines.append("## Failure-Mode Diagnostics\n")
 
@@ -356,8 +976,8 @@ def generate_summary(
     if md:
         lines.append("### Markdown Fence Impact\n")
         lines.append("Some LLM responses wrap code in markdown fences (` ```rust ... ``` `). "
-                      "The eval harness strips these, but text outside fences (e.g. explanations) "
-                      "previously leaked through and caused compilation failures.\n")
+                      "The eval harness extracts the Rust blocks and drops text outside them "
+                      "(see Code Extraction by Style below).\n")
         lines.append("| Group | n | Compile Rate | Pass Rate |")
         lines.append("|-------|---|--------------|-----------|")
         for r in md:
@@ -377,6 +997,37 @@ def generate_summary(
             )
         lines.append("")
 
+    rk = diagnostics.get("response_kinds_by_style")
+    if rk:
+        lines.append("### Response Kinds by Style\n")
+        lines.append(
+            "What each raw response was before compilation. `code_with_prose` responses "
+            "disobeyed the code-only system prompt but still contain code.\n"
+        )
+        lines.append("| Kind | " + " | ".join(rk["styles"]) + " |")
+        lines.append("|------|" + "|".join("---" for _ in rk["styles"]) + "|")
+        for r in rk["rows"]:
+            lines.append(f"| {r['response_kind']} | " + " | ".join(str(r[st]) for st in rk["styles"]) + " |")
+        if "mean_prose_lines" in rk:
+            lines.append(
+                "| *mean prose lines* | "
+                + " | ".join(f"{rk['mean_prose_lines'].get(st, 0):.1f}" for st in rk["styles"]) + " |"
+            )
+            lines.append(f"\nKruskal-Wallis on prose lines across styles: p = {rk['prose_kruskal_p']:.4f}")
+        lines.append("")
+
+    ex = diagnostics.get("extraction_by_style")
+    if ex:
+        lines.append("### Code Extraction by Style\n")
+        lines.append("| Strategy | " + " | ".join(ex["styles"]) + " | Pass Rate |")
+        lines.append("|----------|" + "|".join("---" for _ in ex["styles"]) + "|-----------|")
+        for r in ex["rows"]:
+            lines.append(
+                f"| {r['extraction']} | " + " | ".join(str(r[st]) for st in ex["styles"])
+                + f" | {r['pass_rate']:.3f} |"
+            )
+        lines.append("")
+
     # Worst tasks
     worst = diagnostics.get("worst_tasks", [])
     if worst:
@@ -390,6 +1041,59 @@ def generate_summary(
             )
         lines.append("")
 
+    # Outcome classes
+    oc = diagnostics.get("outcomes_by_style")
+    if oc:
+        lines.append("### Outcome Classes by Style\n")
+        if diagnostics.get("infra_excluded"):
+            lines.append(
+                f"{diagnostics['infra_excluded']} result(s) with outcome `infra_error` "
+                "(harness failures) are excluded from all statistics.\n"
+            )
+        lines.append("| Outcome | " + " | ".join(oc["styles"]) + " |")
+        lines.append("|---------|" + "|".join("---" for _ in oc["styles"]) + "|")
+        for r in oc["rows"]:
+            lines.append(f"| {r['outcome']} | " + " | ".join(str(r[st]) for st in oc["styles"]) + " |")
+        lines.append("")
+
+    # Failure clusters that look like specification problems
+    spec = diagnostics.get("spec_problems", [])
+    if spec:
+        lines.append("### Likely Specification Problems\n")
+        lines.append(
+            "Tests that fail the same way (same normalized panic message and assertion values) "
+            "under every style the task was run with and most models. The task description is probably ambiguous; "
+            "see `failure_clusters.json` for all clusters.\n"
+        )
+        lines.append("| Task | Test | Runs | Models | Failure |")
+        lines.append("|------|------|------|--------|---------|")
+        for r in spec:
+            message = r["message"] if pd.notna(r["message"]) else ""
+            failure = message.splitlines()[0] if message else ""
+            if pd.notna(r["left"]):
+                failure += f" (left `{r['left']}`, right `{r['right']}`)"
+            failure = failure.replace("|", "\\|")
+            lines.append(
+                f"| `{r['task_file']}` | {r['test']} | {r['runs']} | {len(r['models'])} | {failure} |"
+            )
+        lines.append("")
+
+    # Compiler error codes
+    ec = diagnostics.get("error_codes_by_style")
+    if ec:
+        lines.append("### Compiler Error Codes by Style (top 15)\n")
+        lines.append("Occurrences of each rustc error code; the last row is the number of responses per style.\n")
+        lines.append("| Code | " + " | ".join(ec["styles"]) + " | Total |")
+        lines.append("|------|" + "|".join("---" for _ in ec["styles"]) + "|-------|")
+        for r in ec["rows"]:
+            lines.append(
+                f"| {r['code']} | " + " | ".join(str(r[st]) for st in ec["styles"]) + f" | {r['total']} |"
+            )
+        lines.append(
+            "| *responses* | " + " | ".join(str(ec["responses"].get(st, 0)) for st in ec["styles"]) + " | |"
+        )
+        lines.append("")
+
     out_path.write_text("\n".join(lines))
     print(f"Wrote {out_path}")
 
@@ -464,15 +1168,40 @@ def generate_figures(df: pd.DataFrame, agg: pd.DataFrame, fig_dir: Path) -> None
 
 def main() -> None:
     parser = argparse.ArgumentParser(description="Analyze prompting-style experiment results.")
+    parser.add_argument(
+        "--experiment",
+        action="append",
+        default=[],
+        help="Experiment ID under ../results/ to analyze (repeatable; default: latest evaluated)",
+    )
     parser.add_argument(
         "--results",
         type=Path,
-        default=Path(__file__).resolve().parent.parent / "eval" / "results.json",
-        help="Path to results.json (default: ../eval/results.json)",
+        action="append",
+        default=[],
+        help="Explicit path to an eval.json file (repeatable)",
+    )
+    parser.add_argument(
+        "--out-dir",
+        type=Path,
+        default=Path(__file__).resolve().parent,
+        help="Directory for summary.md and figures/ (default: this directory)",
     )
     args = parser.parse_args()
 
-    df = load_results(args.results)
+    experiments: list[str] = list(args.experiment)
+    if not experiments and not args.results:
+        latest = latest_experiment(RESULTS_ROOT)
+        if latest is None:
+            print(f"No evaluated experiments found under {RESULTS_ROOT}", file=sys.stderr)
+            sys.exit(1)
+        print(f"No experiment given; analyzing latest: {latest}")
+        experiments.append(latest)
+    paths = [RESULTS_ROOT / exp / "eval.json" for exp in experiments] + list(args.results)
+
+    df = load_experiments(paths)
+    if "experiment_id" in df.columns:
+        experiments = sorted(df["experiment_id"].unique().tolist())
 
     # Derive columns from eval output format:
     # eval produces: compiled, tests_passed, tests_total, latency_ms, input_tokens, output_tokens
@@ -506,6 +1235,15 @@ def main() -> None:
 
     df["pass"] = df["pass"].astype(float)
 
+    # Harness failures say nothing about the prompting style.
+    infra_excluded = 0
+    if "outcome" in df.columns:
+        infra = df["outcome"] == "infra_error"
+        infra_excluded = int(infra.sum())
+        if infra_excluded:
+            print(f"Excluding {infra_excluded} result(s) with outcome infra_error")
+        df = df.loc[~infra].copy()
+
     styles = sorted(df["style"].unique().tolist())
     print(f"Loaded {len(df)} results across styles: {styles}")
 
@@ -514,10 +1252,32 @@ def main() -> None:
 
     test_results = run_statistical_tests(df, styles)
     diagnostics = diagnose_failures(df)
-
-    analysis_dir = Path(__file__).resolve().parent
-    generate_summary(agg, tier_agg, test_results, diagnostics, analysis_dir / "summary.md")
-    generate_figures(df, agg, analysis_dir / "figures")
+    diagnostics["infra_excluded"] = infra_excluded
+    adaptive = summarize_adaptive(df, load_adaptive(paths))
+    lints = compare_lints(df, styles)
+    formatting = compare_formatting(df, styles)
+    placeholders = compare_placeholders(df, styles)
+    own_tests = compare_own_tests(df, styles)
+    properties = compare_properties(df, styles)
+    visibility = compare_visibility(df, styles)
+    benchmarks = compare_benchmarks(df, styles)
+    flakiness = compare_flakiness(df, styles)
+    clusters = cluster_failures(df)
+    if not clusters.empty:
+        diagnostics["spec_problems"] = clusters[clusters["likely_spec_problem"]].to_dict(orient="records")
+
+    out_dir: Path = args.out_dir
+    out_dir.mkdir(parents=True, exist_ok=True)
+    if not clusters.empty:
+        clusters_path = out_dir / "failure_clusters.json"
+        clusters_path.write_text(clusters.to_json(orient="records", indent=2))
+        print(f"Wrote {clusters_path}")
+    generate_summary(
+        agg, tier_agg, test_results, diagnostics, adaptive, lints, formatting, placeholders,
+        own_tests, properties, visibility, benchmarks, flakiness, experiments,
+        out_dir / "summary.md",
+    )
+    generate_figures(df, agg, out_dir / "figures")
 
     print("Analysis complete.")
 
@@ -0,0 +1,56 @@
+// Prelude of the benchmark harness (see `src/benchmark.rs`), after `rng.rs`.
+// The task's snippets are appended below it: `type Input`, `setup`,
+// `reference_side` and `candidate_side`, plus `SIZES`, `REPEATS`, `SEED` and
+// `SIDE_BUDGET`.
+
+use std::time::{Duration, Instant};
+
+/// One solution and the time its workloads have taken so far.
+struct Side {
+    name: &'static str,
+    run: fn(&Input, usize),
+    used: Duration,
+}
+
+/// Time each side at every size, best of `REPEATS`, printing one JSON line
+/// per measurement as it is taken. A side that has used up `SIDE_BUDGET`
+/// is skipped at the remaining sizes (`"ns": null`).
+fn main() {
+    let mut sides = [
+        Side {
+            name: "reference",
+            run: reference_side,
+            used: Duration::ZERO,
+        },
+        Side {
+            name: "candidate",
+            run: candidate_side,
+            used: Duration::ZERO,
+        },
+    ];
+    for &n in SIZES {
+        let input = setup(&mut Rng::new(SEED ^ n as u64), n);
+        for side in &mut sides {
+            if side.used >= SIDE_BUDGET {
+                println!("{{\"side\":\"{}\",\"n\":{n},\"ns\":null}}", side.name);
+                continue;
+            }
+            let mut best = Duration::MAX;
+            for _ in 0..REPEATS.max(1) {
+                let started = Instant::now();
+                (side.run)(&input, n);
+                let elapsed = started.elapsed();
+                best = best.min(elapsed);
+                side.used += elapsed;
+                if side.used >= SIDE_BUDGET {
+                    break;
+                }
+            }
+            println!(
+                "{{\"side\":\"{}\",\"n\":{n},\"ns\":{}}}",
+                side.name,
+                best.as_nanos()
+            );
+        }
+    }
+}
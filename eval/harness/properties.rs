@@ -0,0 +1,243 @@
+// Prelude of the differential property harness (see `src/properties.rs`),
+// after `rng.rs`. The task's snippets are appended below it: `type Input`,
+// `generate`, `valid`, `expected` and `actual`, plus `CASES`, `MAX_SIZE` and
+// `SEED`.
+
+use std::panic::{self, AssertUnwindSafe};
+
+/// Candidate inputs tried while shrinking a counterexample.
+const MAX_SHRINK_STEPS: usize = 2000;
+
+// ── Shrinking ───────────────────────────────────────────────────────
+
+/// Simpler values to try in place of a failing one, boldest first.
+pub trait Shrink: Sized {
+    fn shrink(&self) -> Vec<Self>;
+}
+
+macro_rules! shrink_int {
+    ($($t:ty)*) => {$(
+        impl Shrink for $t {
+            /// 0, then halfway there, a quarter of the way, …, one step.
+            fn shrink(&self) -> Vec<Self> {
+                let x = *self;
+                let mut out = Vec::new();
+                if x != 0 {
+                    out.push(0);
+                }
+                let mut step = x / 2;
+                while step != 0 {
+                    out.push(x - step);
+                    step /= 2;
+                }
+                out
+            }
+        }
+    )*};
+}
+
+shrink_int!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+impl Shrink for bool {
+    fn shrink(&self) -> Vec<Self> {
+        if *self {
+            vec![false]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl Shrink for char {
+    fn shrink(&self) -> Vec<Self> {
+        if *self == 'a' {
+            Vec::new()
+        } else {
+            vec!['a']
+        }
+    }
+}
+
+impl Shrink for f64 {
+    fn shrink(&self) -> Vec<Self> {
+        [0.0, self.trunc()]
+            .into_iter()
+            .filter(|x| x != self)
+            .collect()
+    }
+}
+
+impl<T: Shrink + Clone> Shrink for Vec<T> {
+    /// Drop chunks of halving size, then shrink single elements.
+    fn shrink(&self) -> Vec<Self> {
+        let mut out = Vec::new();
+        let mut chunk = self.len();
+        while chunk > 0 {
+            let mut start = 0;
+            while start + chunk <= self.len() {
+                let mut v = self.clone();
+                v.drain(start..start + chunk);
+                out.push(v);
+                start += chunk;
+            }
+            chunk /= 2;
+        }
+        for (i, x) in self.iter().enumerate() {
+            for y in x.shrink() {
+                let mut v = self.clone();
+                v[i] = y;
+                out.push(v);
+            }
+        }
+        out
+    }
+}
+
+impl Shrink for String {
+    fn shrink(&self) -> Vec<Self> {
+        let chars: Vec<char> = self.chars().collect();
+        chars.shrink().into_iter().map(|v| v.into_iter().collect()).collect()
+    }
+}
+
+impl<T: Shrink + Clone> Shrink for Option<T> {
+    fn shrink(&self) -> Vec<Self> {
+        match self {
+            None => Vec::new(),
+            Some(x) => std::iter::once(None).chain(x.shrink().into_iter().map(Some)).collect(),
+        }
+    }
+}
+
+macro_rules! shrink_tuple {
+    ($(($($name:ident $i:tt),+))*) => {$(
+        impl<$($name: Shrink + Clone),+> Shrink for ($($name,)+) {
+            /// Shrink one component at a time.
+            fn shrink(&self) -> Vec<Self> {
+                let mut out = Vec::new();
+                $(
+                    for y in self.$i.shrink() {
+                        let mut t = self.clone();
+                        t.$i = y;
+                        out.push(t);
+                    }
+                )+
+                out
+            }
+        }
+    )*};
+}
+
+shrink_tuple!((A 0, B 1) (A 0, B 1, C 2) (A 0, B 1, C 2, D 3));
+
+// ── Comparison ──────────────────────────────────────────────────────
+
+/// What one side returned, as `{:?}`, or what it panicked with.
+enum Output {
+    Value(String),
+    Panic(String),
+}
+
+impl Output {
+    fn of(side: fn(&Input) -> String, input: &Input) -> Self {
+        match panic::catch_unwind(AssertUnwindSafe(|| side(input))) {
+            Ok(value) => Output::Value(value),
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                Output::Panic(message)
+            }
+        }
+    }
+
+    fn describe(self) -> String {
+        match self {
+            Output::Value(value) => value,
+            Output::Panic(message) => format!("panicked: {message}"),
+        }
+    }
+}
+
+/// The reference's and the candidate's output if they differ. Two panics
+/// agree whatever their messages.
+fn diverges(input: &Input) -> Option<(String, String)> {
+    let reference = Output::of(expected, input);
+    let candidate = Output::of(actual, input);
+    match (&reference, &candidate) {
+        (Output::Value(r), Output::Value(c)) if r == c => None,
+        (Output::Panic(_), Output::Panic(_)) => None,
+        _ => Some((reference.describe(), candidate.describe())),
+    }
+}
+
+/// Greedily replace `input` by the first simpler valid input that still
+/// diverges, until none does or the step budget runs out.
+fn shrink(mut input: Input, mut outputs: (String, String)) -> (Input, (String, String)) {
+    let mut steps = 0;
+    'search: loop {
+        for candidate in input.shrink() {
+            steps += 1;
+            if steps > MAX_SHRINK_STEPS {
+                break 'search;
+            }
+            if !valid(&candidate) {
+                continue;
+            }
+            if let Some(o) = diverges(&candidate) {
+                input = candidate;
+                outputs = o;
+                continue 'search;
+            }
+        }
+        break;
+    }
+    (input, outputs)
+}
+
+fn json(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Run `CASES` inputs, their size growing from 0 to `MAX_SIZE`, and print
+/// one JSON report: the valid inputs run and the first counterexample.
+fn main() {
+    panic::set_hook(Box::new(|_| {}));
+    let mut cases = 0;
+    for case in 0..CASES {
+        let seed = SEED.wrapping_add(case as u64);
+        let size = case * MAX_SIZE / CASES.saturating_sub(1).max(1);
+        let input = generate(&mut Rng::new(seed), size);
+        if !valid(&input) {
+            continue;
+        }
+        cases += 1;
+        let Some(outputs) = diverges(&input) else {
+            continue;
+        };
+        let original = format!("{input:?}");
+        let (shrunk, (expected, actual)) = shrink(input, outputs);
+        println!(
+            "{{\"cases\":{cases},\"counterexample\":{{\"case\":{case},\"seed\":{seed},\
+             \"input\":{},\"shrunk_input\":{},\"expected\":{},\"actual\":{}}}}}",
+            json(&original),
+            json(&format!("{shrunk:?}")),
+            json(&expected),
+            json(&actual),
+        );
+        return;
+    }
+    println!("{{\"cases\":{cases},\"counterexample\":null}}");
+}
@@ -0,0 +1,52 @@
+// Seeded random inputs, shared by the harnesses that run a candidate beside
+// the reference solution (see `src/reference.rs`).
+
+/// splitmix64: small, seedable and the same on every platform.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`; 0 if `n` is 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            0
+        } else {
+            self.next_u64() % n
+        }
+    }
+
+    /// Uniform in `lo..hi`; `lo` if the range is empty.
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        lo.wrapping_add(self.below(hi.wrapping_sub(lo).max(0) as u64) as i64)
+    }
+
+    pub fn bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    /// Up to `max_len` characters drawn from `alphabet`.
+    pub fn string(&mut self, alphabet: &str, max_len: usize) -> String {
+        let chars: Vec<char> = alphabet.chars().collect();
+        let len = self.below(max_len as u64 + 1);
+        (0..len)
+            .map(|_| chars[self.below(chars.len() as u64) as usize])
+            .collect()
+    }
+
+    /// Up to `max_len` elements made by `element`.
+    pub fn vec<T>(&mut self, max_len: usize, mut element: impl FnMut(&mut Rng) -> T) -> Vec<T> {
+        let len = self.below(max_len as u64 + 1);
+        (0..len).map(|_| element(self)).collect()
+    }
+}
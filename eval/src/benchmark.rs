@@ -0,0 +1,250 @@
+//! Scaling benchmarks against the task's reference solution. A task opts in
+//! with a `[task.benchmark]` table (see `BenchmarkSpec`): one workload is
+//! timed at several sizes on the candidate and on the reference, and a
+//! power law `time ∝ n^k` is fitted to each, so that e.g. a linear scan in
+//! an "O(1) get" cache shows up as quadratic growth of the whole workload.
+//!
+//! The harness (see `reference`) is `harness/rng.rs` and
+//! `harness/benchmark.rs` followed by the task's snippets. It prints one
+//! JSON line per measurement, so a run cut off by the time-out still counts
+//! the sizes it finished.
+
+use crate::{cargo_build, reference, run_test_binary, sandbox, BenchmarkSpec, EvalOptions};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const PRELUDE: &str = include_str!("../harness/benchmark.rs");
+
+/// How far the fitted exponent may exceed the expected one before the
+/// response is flagged: half a power of `n`, well above timing noise and
+/// the `log n` factors that a power law absorbs.
+const EXPONENT_MARGIN: f64 = 0.5;
+
+/// Growth of a workload's time with its size, as the nearest power of `n`;
+/// `n log n` reads as linear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Growth {
+    Constant,
+    Linear,
+    Quadratic,
+    Cubic,
+}
+
+impl Growth {
+    fn exponent(self) -> f64 {
+        match self {
+            Growth::Constant => 0.0,
+            Growth::Linear => 1.0,
+            Growth::Quadratic => 2.0,
+            Growth::Cubic => 3.0,
+        }
+    }
+
+    fn nearest(exponent: f64) -> Self {
+        match exponent {
+            k if k < 0.5 => Growth::Constant,
+            k if k < 1.5 => Growth::Linear,
+            k if k < 2.5 => Growth::Quadratic,
+            _ => Growth::Cubic,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BenchStatus {
+    /// The task has no benchmark, the library didn't compile or the build
+    /// timed out.
+    #[default]
+    NotRun,
+    Measured,
+    /// The harness didn't compile against the candidate.
+    CompileError,
+    /// The harness was killed at `--bench-timeout`; sizes it finished are
+    /// kept.
+    Timeout,
+    /// The harness died before finishing, e.g. on a panic.
+    Crash,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Benchmark {
+    pub bench_status: BenchStatus,
+    /// Best time of each side at each size, in nanoseconds; `None` where a
+    /// side ran out of time.
+    pub bench_timings: Vec<Timing>,
+    /// Candidate over reference time at the largest size both finished.
+    pub bench_time_ratio: Option<f64>,
+    /// Fitted exponents `k` of `time ∝ n^k`.
+    pub bench_exponent: Option<f64>,
+    pub bench_reference_exponent: Option<f64>,
+    pub bench_growth: Option<Growth>,
+    /// The candidate's time grows faster than the task's `expected` growth
+    /// (or, without one, the reference's) by more than `EXPONENT_MARGIN`,
+    /// or it ran out of time at a size the reference finished.
+    pub bench_flagged: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Timing {
+    pub n: usize,
+    pub reference_ns: Option<u64>,
+    pub candidate_ns: Option<u64>,
+}
+
+/// One line of the harness's output.
+#[derive(Deserialize)]
+struct Measurement {
+    side: String,
+    n: usize,
+    ns: Option<u64>,
+}
+
+/// Build the harness into the temp crate at `crate_dir`, whose library has
+/// already been built and which has the reference added, and run it under
+/// `timeout`. Returns the result and the files written to the shared target
+/// dir.
+pub fn run(
+    spec: &BenchmarkSpec,
+    crate_dir: &Path,
+    timeout: Duration,
+    opts: &EvalOptions,
+    target_dir: &Path,
+) -> std::io::Result<(Benchmark, Vec<PathBuf>)> {
+    // Each side gets a share of the time-out, leaving room for the set-up.
+    reference::add_target(crate_dir, "benchmark", &harness(spec, timeout * 2 / 5))?;
+    let build = cargo_build(crate_dir, &["benchmark"], opts.compile_timeout, opts, target_dir)?;
+    let mut bench = Benchmark::default();
+    if build.timed_out {
+        return Ok((bench, build.artifacts));
+    }
+    let Some(exe) = build.executables.get("benchmark").filter(|_| build.success) else {
+        bench.bench_status = BenchStatus::CompileError;
+        return Ok((bench, build.artifacts));
+    };
+
+    let output = run_test_binary(exe, timeout, opts)?;
+    bench.bench_timings = spec
+        .sizes
+        .iter()
+        .map(|&n| Timing {
+            n,
+            reference_ns: None,
+            candidate_ns: None,
+        })
+        .collect();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for m in stdout.lines().filter_map(|l| serde_json::from_str::<Measurement>(l).ok()) {
+        let Some(timing) = bench.bench_timings.iter_mut().find(|t| t.n == m.n) else {
+            continue;
+        };
+        match m.side.as_str() {
+            "reference" => timing.reference_ns = m.ns,
+            "candidate" => timing.candidate_ns = m.ns,
+            _ => {}
+        }
+    }
+    bench.bench_status = match output.signal {
+        _ if output.timed_out => BenchStatus::Timeout,
+        Some(sig) if sandbox::is_cpu_limit(sig) => BenchStatus::Timeout,
+        _ if !output.success => BenchStatus::Crash,
+        _ => BenchStatus::Measured,
+    };
+
+    let points = |side: fn(&Timing) -> Option<u64>| -> Vec<(f64, f64)> {
+        bench
+            .bench_timings
+            .iter()
+            .filter_map(|t| side(t).filter(|&ns| ns > 0).map(|ns| (t.n as f64, ns as f64)))
+            .collect()
+    };
+    bench.bench_exponent = fit_exponent(&points(|t| t.candidate_ns));
+    bench.bench_reference_exponent = fit_exponent(&points(|t| t.reference_ns));
+    bench.bench_growth = bench.bench_exponent.map(Growth::nearest);
+    bench.bench_time_ratio = bench
+        .bench_timings
+        .iter()
+        .rev()
+        .find_map(|t| match (t.reference_ns, t.candidate_ns) {
+            (Some(r), Some(c)) if r > 0 => Some(c as f64 / r as f64),
+            _ => None,
+        });
+
+    let expected = spec
+        .expected
+        .map(Growth::exponent)
+        .or(bench.bench_reference_exponent);
+    let too_steep = bench
+        .bench_exponent
+        .zip(expected)
+        .is_some_and(|(k, expected)| k > expected + EXPONENT_MARGIN);
+    let ran_out = bench
+        .bench_timings
+        .iter()
+        .any(|t| t.reference_ns.is_some() && t.candidate_ns.is_none());
+    bench.bench_flagged = bench.bench_status != BenchStatus::Crash && (too_steep || ran_out);
+    Ok((bench, build.artifacts))
+}
+
+/// Least-squares slope of `ln time` against `ln n`; needs two sizes.
+fn fit_exponent(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+    let logs: Vec<(f64, f64)> = points.iter().map(|&(n, t)| (n.ln(), t.ln())).collect();
+    let count = logs.len() as f64;
+    let mean_x = logs.iter().map(|p| p.0).sum::<f64>() / count;
+    let mean_y = logs.iter().map(|p| p.1).sum::<f64>() / count;
+    let sxx: f64 = logs.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let sxy: f64 = logs.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    (sxx > 0.0).then(|| sxy / sxx)
+}
+
+/// The preludes followed by the task's snippets.
+fn harness(spec: &BenchmarkSpec, side_budget: Duration) -> String {
+    let BenchmarkSpec {
+        sizes,
+        input,
+        setup,
+        run,
+        helpers,
+        repeats,
+        ..
+    } = spec;
+    let rng = reference::RNG;
+    let budget_ms = side_budget.as_millis();
+    format!(
+        "{rng}{PRELUDE}
+// ── Task ────────────────────────────────────────────────────────────
+
+const SIZES: &[usize] = &{sizes:?};
+const REPEATS: usize = {repeats};
+const SEED: u64 = 0;
+const SIDE_BUDGET: Duration = Duration::from_millis({budget_ms});
+
+{helpers}
+
+type Input = {input};
+
+fn setup(rng: &mut Rng, n: usize) -> Input {{
+{setup}
+}}
+
+fn reference_side(input: &Input, n: usize) {{
+    use reference as sol;
+    std::hint::black_box({{
+{run}
+    }});
+}}
+
+fn candidate_side(input: &Input, n: usize) {{
+    use solution as sol;
+    std::hint::black_box({{
+{run}
+    }});
+}}
+"
+    )
+}
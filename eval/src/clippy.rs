@@ -0,0 +1,161 @@
+//! Clippy lint counts, as a measure of how idiomatic a solution is.
+//!
+//! Every library that compiles is linted with the same pinned profile: the
+//! six groups below are passed with `--force-warn`, which also overrides any
+//! `#![allow(...)]` in the solution, and clippy reads an empty `clippy.toml`
+//! from the temp crate. Only the library is linted, not the harness tests.
+
+use crate::{sandbox, wait_with_timeout, EvalOptions};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+pub const GROUPS: [&str; 6] = [
+    "style",
+    "complexity",
+    "perf",
+    "correctness",
+    "suspicious",
+    "pedantic",
+];
+
+/// Lints kept in `clippy_top`.
+const TOP_LINTS: usize = 5;
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Lints {
+    /// Clippy ran to completion. If not, every count is zero and the
+    /// response should be left out of lint statistics.
+    pub clippy_ran: bool,
+    /// Lint hits per group.
+    pub clippy_style: usize,
+    pub clippy_complexity: usize,
+    pub clippy_perf: usize,
+    pub clippy_correctness: usize,
+    pub clippy_suspicious: usize,
+    pub clippy_pedantic: usize,
+    /// The most frequent lints, most frequent first.
+    pub clippy_top: Vec<LintCount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintCount {
+    /// Lint name without the `clippy::` prefix.
+    pub lint: String,
+    pub count: usize,
+}
+
+/// Whether `cargo clippy` is installed.
+pub fn available() -> bool {
+    Command::new("cargo")
+        .args(["clippy", "--version"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// Lint the library of the temp crate at `crate_dir`. Returns the counts
+/// and the files clippy wrote to the shared target dir.
+pub fn lint(
+    crate_dir: &Path,
+    timeout: Duration,
+    opts: &EvalOptions,
+    target_dir: &Path,
+) -> std::io::Result<(Lints, Vec<PathBuf>)> {
+    fs::write(crate_dir.join("clippy.toml"), "")?;
+    let mut cmd = Command::new("cargo");
+    sandbox::own_process_group(&mut cmd)
+        .args(["clippy", "--lib", "--offline", "--message-format=json"])
+        .arg(format!("--jobs={}", opts.cpus_per_job))
+        .arg("--");
+    for group in GROUPS {
+        cmd.args(["--force-warn", &format!("clippy::{group}")]);
+    }
+    let mut child = cmd
+        .env("CARGO_TARGET_DIR", target_dir)
+        .env("CLIPPY_CONF_DIR", crate_dir)
+        // Check builds are incremental by default, and the session dirs
+        // would pile up in the shared target dir.
+        .env("CARGO_INCREMENTAL", "0")
+        .current_dir(crate_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let output = wait_with_timeout(&mut child, timeout);
+
+    let mut artifacts = Vec::new();
+    let mut hits: Vec<String> = Vec::new();
+    let mut group_of: HashMap<String, &'static str> = HashMap::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Ok(msg) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if msg["reason"] == "compiler-artifact" {
+            if let Some(files) = msg["filenames"].as_array() {
+                artifacts.extend(files.iter().filter_map(|f| f.as_str()).map(PathBuf::from));
+            }
+            continue;
+        }
+        if msg["reason"] != "compiler-message" {
+            continue;
+        }
+        let message = &msg["message"];
+        let Some(lint) = message["code"]["code"]
+            .as_str()
+            .and_then(|c| c.strip_prefix("clippy::"))
+        else {
+            continue;
+        };
+        // Clippy names the group that enabled a lint ("`--force-warn
+        // clippy::x` implied by `--force-warn clippy::style`") only on the
+        // lint's first hit.
+        if let Some(children) = message["children"].as_array() {
+            let group = children
+                .iter()
+                .filter_map(|c| c["message"].as_str())
+                .find_map(|text| GROUPS.into_iter().find(|g| text.ends_with(&implied_by(g))));
+            if let Some(group) = group {
+                group_of.insert(lint.to_string(), group);
+            }
+        }
+        hits.push(lint.to_string());
+    }
+
+    let mut lints = Lints {
+        clippy_ran: output.success && !output.timed_out,
+        ..Lints::default()
+    };
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for lint in &hits {
+        *counts.entry(lint).or_default() += 1;
+        let field = match group_of.get(lint).copied() {
+            Some("style") => &mut lints.clippy_style,
+            Some("complexity") => &mut lints.clippy_complexity,
+            Some("perf") => &mut lints.clippy_perf,
+            Some("correctness") => &mut lints.clippy_correctness,
+            Some("suspicious") => &mut lints.clippy_suspicious,
+            Some("pedantic") => &mut lints.clippy_pedantic,
+            _ => continue,
+        };
+        *field += 1;
+    }
+    let mut top: Vec<LintCount> = counts
+        .into_iter()
+        .map(|(lint, count)| LintCount {
+            lint: lint.to_string(),
+            count,
+        })
+        .collect();
+    top.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.lint.cmp(&b.lint)));
+    top.truncate(TOP_LINTS);
+    lints.clippy_top = top;
+    Ok((lints, artifacts))
+}
+
+fn implied_by(group: &str) -> String {
+    format!("implied by `--force-warn clippy::{group}`")
+}
@@ -0,0 +1,304 @@
+//! Checks for a task's `must_use` and `forbidden` lists.
+//!
+//! Items are matched against paths in the syntax tree, so comments, doc
+//! comments and string literals never count. Each path is first resolved
+//! through the file's `use` declarations, renames and module imports
+//! included: after `use std::collections::BTreeMap as Map;`, `Map::new()`
+//! is a use of `BTreeMap`. Names the file defines itself shadow imports and
+//! never match. The item `unsafe` matches unsafe blocks, functions, impls
+//! and traits; the solution is also built under `#![forbid(unsafe_code)]`
+//! when it is forbidden, so the compiler enforces it.
+
+use crate::metrics::is_test_code;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, Token, UseTree};
+
+/// The constraint item that stands for unsafe code rather than a path.
+pub const UNSAFE: &str = "unsafe";
+
+/// Crate-level attribute prepended to solutions that must not use `unsafe`.
+/// It goes on the first line so diagnostics keep their line numbers.
+pub const FORBID_UNSAFE: &str = "#![forbid(unsafe_code)] ";
+
+/// One place where a constraint item is used.
+#[derive(Debug, Clone, Serialize)]
+pub struct Evidence {
+    pub item: String,
+    /// The path as written, resolved through `use` declarations, or
+    /// `unsafe`.
+    pub path: String,
+    /// 1-based position in the solution.
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct Check {
+    pub must_use_satisfied: bool,
+    /// First use of each `must_use` item; items never used are absent.
+    pub must_use: Vec<Evidence>,
+    pub forbidden_violated: bool,
+    /// Every use of a forbidden item.
+    pub forbidden: Vec<Evidence>,
+}
+
+/// Check `code` against the task's constraints. Code that doesn't parse
+/// satisfies no `must_use` item and violates nothing.
+pub fn check(code: &str, must_use: &[String], forbidden: &[String]) -> Check {
+    let Ok(file) = syn::parse_file(code) else {
+        return Check {
+            must_use_satisfied: must_use.is_empty(),
+            ..Check::default()
+        };
+    };
+
+    let mut scope = Scope::default();
+    scope.visit_file(&file);
+
+    let mut finder = Finder {
+        scope: &scope,
+        items: must_use.iter().chain(forbidden).map(String::as_str).collect(),
+        found: Vec::new(),
+    };
+    finder.visit_file(&file);
+    let found = finder.found;
+
+    let mut must_use_evidence: Vec<Evidence> = Vec::new();
+    for item in must_use {
+        if let Some(e) = found.iter().find(|e| &e.item == item) {
+            must_use_evidence.push(e.clone());
+        }
+    }
+    let forbidden_evidence: Vec<Evidence> = found
+        .into_iter()
+        .filter(|e| forbidden.contains(&e.item))
+        .collect();
+
+    Check {
+        must_use_satisfied: must_use_evidence.len() == must_use.len(),
+        must_use: must_use_evidence,
+        forbidden_violated: !forbidden_evidence.is_empty(),
+        forbidden: forbidden_evidence,
+    }
+}
+
+// ── Name resolution ─────────────────────────────────────────────────
+
+/// What the file's `use` declarations bring into scope, and the names it
+/// defines itself. Scoping is flattened to the whole file, which is enough
+/// for single-file solutions.
+#[derive(Default)]
+struct Scope {
+    imports: HashMap<String, Vec<String>>,
+    local: HashSet<String>,
+}
+
+impl Scope {
+    fn add_use(&mut self, tree: &UseTree, prefix: &mut Vec<String>) {
+        match tree {
+            UseTree::Path(p) => {
+                prefix.push(p.ident.to_string());
+                self.add_use(&p.tree, prefix);
+                prefix.pop();
+            }
+            UseTree::Name(n) if n.ident == "self" => {
+                if let Some(last) = prefix.last() {
+                    self.imports.insert(last.clone(), prefix.clone());
+                }
+            }
+            UseTree::Name(n) => {
+                let mut path = prefix.clone();
+                path.push(n.ident.to_string());
+                self.imports.insert(n.ident.to_string(), path);
+            }
+            UseTree::Rename(r) => {
+                let mut path = prefix.clone();
+                if r.ident != "self" {
+                    path.push(r.ident.to_string());
+                }
+                self.imports.insert(r.rename.to_string(), path);
+            }
+            UseTree::Group(g) => {
+                for tree in &g.items {
+                    self.add_use(tree, prefix);
+                }
+            }
+            // What a glob brings in is unknown without the imported crate.
+            UseTree::Glob(_) => {}
+        }
+    }
+
+    /// Expand the first segment of `path` through the imports until it no
+    /// longer names one; local names resolve to `crate::…`.
+    fn resolve(&self, path: &syn::Path) -> Vec<String> {
+        let mut segments: Vec<String> =
+            path.segments.iter().map(|s| s.ident.to_string()).collect();
+        if path.leading_colon.is_some() {
+            return segments;
+        }
+        // Bounded, since `use a::b as c; use c::d as a;` would otherwise loop.
+        for _ in 0..8 {
+            let Some(first) = segments.first().cloned() else { break };
+            let Some(target) = self.imports.get(&first) else {
+                if self.local.contains(&first) {
+                    segments.insert(0, "crate".to_string());
+                }
+                break;
+            };
+            segments.splice(0..1, target.iter().cloned());
+            // `use regex;` or `use regex::Regex as regex;` lead outside the file.
+            if target.first() == Some(&first) {
+                break;
+            }
+        }
+        segments
+    }
+}
+
+impl<'ast> Visit<'ast> for Scope {
+    fn visit_item(&mut self, node: &'ast syn::Item) {
+        let name = match node {
+            syn::Item::Struct(i) => Some(&i.ident),
+            syn::Item::Enum(i) => Some(&i.ident),
+            syn::Item::Union(i) => Some(&i.ident),
+            syn::Item::Trait(i) => Some(&i.ident),
+            syn::Item::Type(i) => Some(&i.ident),
+            syn::Item::Mod(i) => Some(&i.ident),
+            syn::Item::Fn(i) => Some(&i.sig.ident),
+            _ => None,
+        };
+        if let Some(name) = name {
+            self.local.insert(name.to_string());
+        }
+        visit::visit_item(self, node);
+    }
+
+    fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+        self.add_use(&node.tree, &mut Vec::new());
+    }
+}
+
+// ── Matching ────────────────────────────────────────────────────────
+
+struct Finder<'a> {
+    scope: &'a Scope,
+    items: Vec<&'a str>,
+    found: Vec<Evidence>,
+}
+
+impl Finder<'_> {
+    fn record(&mut self, item: &str, path: String, span: proc_macro2::Span) {
+        let start = span.start();
+        self.found.push(Evidence {
+            item: item.to_string(),
+            path,
+            line: start.line,
+            column: start.column + 1,
+        });
+    }
+
+    fn unsafe_code(&mut self, span: proc_macro2::Span) {
+        if self.items.contains(&UNSAFE) {
+            self.record(UNSAFE, UNSAFE.to_string(), span);
+        }
+    }
+}
+
+/// Whether `item` (a name or a `::`-separated path) occurs as consecutive
+/// segments of `path`.
+fn path_matches(path: &[String], item: &str) -> bool {
+    let item: Vec<&str> = item.split("::").collect();
+    path.windows(item.len())
+        .any(|w| w.iter().zip(&item).all(|(a, b)| a == b))
+}
+
+impl<'ast> Visit<'ast> for Finder<'_> {
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        if !is_test_code(&node.attrs) {
+            visit::visit_item_mod(self, node);
+        }
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        if is_test_code(&node.attrs) {
+            return;
+        }
+        if let Some(token) = node.sig.unsafety {
+            self.unsafe_code(token.span);
+        }
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        if is_test_code(&node.attrs) {
+            return;
+        }
+        if let Some(token) = node.sig.unsafety {
+            self.unsafe_code(token.span);
+        }
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_trait_item_fn(&mut self, node: &'ast syn::TraitItemFn) {
+        if let Some(token) = node.sig.unsafety {
+            self.unsafe_code(token.span);
+        }
+        visit::visit_trait_item_fn(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        if is_test_code(&node.attrs) {
+            return;
+        }
+        if let Some(token) = node.unsafety {
+            self.unsafe_code(token.span);
+        }
+        visit::visit_item_impl(self, node);
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+        if let Some(token) = node.unsafety {
+            self.unsafe_code(token.span);
+        }
+        visit::visit_item_trait(self, node);
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &'ast syn::ExprUnsafe) {
+        self.unsafe_code(node.unsafe_token.span);
+        visit::visit_expr_unsafe(self, node);
+    }
+
+    /// An import alone uses nothing; the paths that go through it do.
+    fn visit_item_use(&mut self, _node: &'ast syn::ItemUse) {}
+
+    fn visit_path(&mut self, node: &'ast syn::Path) {
+        let resolved = self.scope.resolve(node);
+        if resolved.first().map(String::as_str) != Some("crate") {
+            let hits: Vec<&str> = self
+                .items
+                .iter()
+                .copied()
+                .filter(|item| *item != UNSAFE && path_matches(&resolved, item))
+                .collect();
+            for item in hits {
+                self.record(item, resolved.join("::"), node.span());
+            }
+        }
+        visit::visit_path(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        // As in `metrics`: arguments of expression-like macros are
+        // ordinary expressions, so `vec![BTreeMap::new()]` counts.
+        if let Ok(args) = node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        visit::visit_macro(self, node);
+    }
+}
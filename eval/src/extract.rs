@@ -0,0 +1,215 @@
+//! Pulling the solution out of a model's response.
+//!
+//! Fenced blocks are chosen by language tag and by whether they parse:
+//! blocks tagged `rust`/`rs` or untagged that parse as a Rust file are
+//! merged in order, and blocks in other languages (`text`, `bash`, `toml`,
+//! …) are dropped. Unfenced responses have leading and trailing prose cut
+//! off. The strategy that produced the code is recorded with each result,
+//! along with how much prose the response has outside its code.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// No fences, and the whole response parses (or nothing better was
+    /// found): used as is.
+    Raw,
+    /// No fences; prose before the first item and after the last was cut.
+    TrimmedProse,
+    /// Exactly one fenced Rust block parses.
+    SingleBlock,
+    /// Several fenced Rust blocks parse; they were concatenated, with
+    /// repeated blocks and `use` lines dropped.
+    MergedBlocks,
+    /// No fenced Rust block parses; the largest one was taken so the
+    /// compiler can report what is wrong with it.
+    UnparsedBlock,
+}
+
+#[derive(Debug, Clone)]
+pub struct Extraction {
+    pub code: String,
+    pub strategy: Strategy,
+    pub had_markdown_fences: bool,
+    /// The response contains something meant as code, even if it doesn't
+    /// parse.
+    pub has_code: bool,
+    /// Non-blank lines outside fences and outside the code of an unfenced
+    /// response, and their characters (trimmed).
+    pub prose_lines: usize,
+    pub prose_chars: usize,
+}
+
+struct Block<'a> {
+    /// Lowercased language tag up to the first non-identifier character,
+    /// so ```` ```rust,ignore ```` is `rust`; empty if untagged.
+    lang: String,
+    lines: Vec<&'a str>,
+}
+
+impl Block<'_> {
+    fn is_rust(&self) -> bool {
+        matches!(self.lang.as_str(), "rust" | "rs" | "")
+    }
+
+    fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+}
+
+pub fn extract(raw: &str) -> Extraction {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut outside: Vec<&str> = Vec::new();
+    let mut open: Option<Block> = None;
+    let mut had_fences = false;
+
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if let Some(info) = trimmed.strip_prefix("```") {
+            had_fences = true;
+            match open.take() {
+                Some(block) => blocks.push(block),
+                None => {
+                    let lang = info
+                        .trim()
+                        .chars()
+                        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+                        .collect::<String>()
+                        .to_lowercase();
+                    open = Some(Block {
+                        lang,
+                        lines: Vec::new(),
+                    });
+                }
+            }
+            continue;
+        }
+        match open.as_mut() {
+            Some(block) => block.lines.push(line),
+            None => outside.push(line),
+        }
+    }
+    // A block left open runs to the end, as in a truncated response.
+    blocks.extend(open);
+
+    let rust_blocks: Vec<&Block> = blocks
+        .iter()
+        .filter(|b| b.is_rust() && b.lines.iter().any(|l| !l.trim().is_empty()))
+        .collect();
+    let parsed: Vec<String> = rust_blocks
+        .iter()
+        .map(|b| b.text())
+        .filter(|text| syn::parse_file(text).is_ok_and(|f| !f.items.is_empty()))
+        .collect();
+
+    let (code, strategy, prose) = match parsed.len() {
+        1 => {
+            let code = parsed.into_iter().next().unwrap_or_default();
+            (code, Strategy::SingleBlock, outside)
+        }
+        n if n > 1 => (merge(&parsed), Strategy::MergedBlocks, outside),
+        _ => match rust_blocks.iter().max_by_key(|b| b.lines.len()) {
+            Some(block) => (block.text(), Strategy::UnparsedBlock, outside),
+            // No Rust blocks at all (or no fences): the code, if any, is
+            // in the prose.
+            None => strip_prose(&outside),
+        },
+    };
+
+    let has_code = match strategy {
+        Strategy::Raw => {
+            syn::parse_file(&code).is_ok_and(|f| !f.items.is_empty()) || looks_like_code(&code)
+        }
+        _ => true,
+    };
+    let prose: Vec<&str> = prose.into_iter().map(str::trim).filter(|l| !l.is_empty()).collect();
+    Extraction {
+        code,
+        strategy,
+        had_markdown_fences: had_fences,
+        has_code,
+        prose_lines: prose.len(),
+        prose_chars: prose.iter().map(|l| l.chars().count()).sum(),
+    }
+}
+
+/// Concatenate parsed blocks, skipping blocks repeated verbatim and `use`
+/// lines already seen, which would otherwise fail as duplicate definitions.
+fn merge(blocks: &[String]) -> String {
+    let mut seen_blocks: Vec<&str> = Vec::new();
+    let mut seen_uses: Vec<&str> = Vec::new();
+    let mut out: Vec<&str> = Vec::new();
+    for block in blocks {
+        if seen_blocks.contains(&block.trim()) {
+            continue;
+        }
+        seen_blocks.push(block.trim());
+        if !out.is_empty() {
+            out.push("");
+        }
+        for line in block.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("use ") && trimmed.ends_with(';') {
+                if seen_uses.contains(&trimmed) {
+                    continue;
+                }
+                seen_uses.push(trimmed);
+            }
+            out.push(line);
+        }
+    }
+    out.join("\n")
+}
+
+/// Cut prose before the first line that starts an item and after the last
+/// line that ends one, both at column 0. Candidate ends are tried from the
+/// last one backwards until the range parses. Returns the code, the
+/// strategy and the prose lines.
+fn strip_prose<'a>(lines: &[&'a str]) -> (String, Strategy, Vec<&'a str>) {
+    let whole = lines.join("\n");
+    if syn::parse_file(&whole).is_ok() {
+        return (whole, Strategy::Raw, Vec::new());
+    }
+    let Some(start) = lines.iter().position(|l| starts_item(l)) else {
+        return (whole, Strategy::Raw, lines.to_vec());
+    };
+    let ends: Vec<usize> = (start..lines.len()).rev().filter(|&i| ends_item(lines[i])).collect();
+    for &end in &ends {
+        let candidate = lines[start..=end].join("\n");
+        if syn::parse_file(&candidate).is_ok_and(|f| !f.items.is_empty()) {
+            let prose = [&lines[..start], &lines[end + 1..]].concat();
+            return (candidate, Strategy::TrimmedProse, prose);
+        }
+    }
+    // Nothing parses: keep everything, but still count what surrounds the
+    // broken code as prose.
+    let end = ends.first().copied().unwrap_or(lines.len() - 1);
+    let prose = [&lines[..start], &lines[end + 1..]].concat();
+    (whole, Strategy::Raw, prose)
+}
+
+/// Whether unparseable text still looks like an attempt at code: an item
+/// start followed by an item end.
+fn looks_like_code(text: &str) -> bool {
+    let lines: Vec<&str> = text.lines().collect();
+    lines
+        .iter()
+        .position(|l| starts_item(l))
+        .is_some_and(|start| lines[start..].iter().any(|l| ends_item(l)))
+}
+
+/// A line at column 0 that can begin an item, its attributes or its docs.
+fn starts_item(line: &str) -> bool {
+    const STARTS: [&str; 19] = [
+        "use ", "pub ", "pub(", "fn ", "struct ", "enum ", "impl", "trait ", "mod ", "const ",
+        "static ", "type ", "#[", "#!", "//", "extern ", "macro_rules!", "unsafe ", "async ",
+    ];
+    STARTS.iter().any(|s| line.starts_with(s))
+}
+
+/// A line at column 0 that can close an item.
+fn ends_item(line: &str) -> bool {
+    let line = line.trim_end();
+    !line.starts_with(char::is_whitespace) && (line.ends_with('}') || line.ends_with(';'))
+}
@@ -0,0 +1,148 @@
+//! Reruns of the task's test binaries, to tell flaky tests from failing
+//! ones. With `--reruns K`, every binary that ran for the score runs K more
+//! times, each time as a new process (so `HashMap` seeds differ) and under a
+//! different schedule: one test thread, the job's CPUs or twice that, some
+//! runs alongside busy threads that compete for the same CPUs. The busy
+//! threads are pinned to the job's own CPUs, so they never slow other jobs;
+//! where that isn't possible, those runs go without them.
+
+use crate::{parse_test_output, run_test_binary_on, sandbox, EvalOptions};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Reruns {
+    /// Extra runs of each test binary; 0 unless `--reruns` is given.
+    pub reruns: usize,
+    /// Reruns that competed with busy threads; fewer than scheduled when
+    /// several jobs run and the job couldn't be pinned to CPUs of its own.
+    pub contended_reruns: usize,
+    /// Runs each test passed, out of `reruns + 1` (the scored run included).
+    /// A test that didn't report, e.g. because its binary timed out, failed.
+    pub test_pass_counts: BTreeMap<String, usize>,
+    /// Tests that passed in some runs but not all.
+    pub flaky_tests: Vec<String>,
+    /// Some test is flaky; `tests_passed` still counts the scored run only.
+    pub flaky: bool,
+}
+
+/// How one rerun is scheduled.
+struct Schedule {
+    threads: usize,
+    contended: bool,
+}
+
+/// The schedule of rerun `i`: thread counts cycle with period 3 and
+/// contention with period 2, so six reruns cover every combination.
+fn schedule(i: usize, cpus: usize) -> Schedule {
+    Schedule {
+        threads: [1, 2 * cpus, cpus][i % 3],
+        contended: i.is_multiple_of(2),
+    }
+}
+
+/// Rerun `binaries` `reruns` times, each under the task's `time_limit` as in
+/// the scored run, whose `results` count as the first run.
+pub fn rerun(
+    binaries: &[PathBuf],
+    results: &HashMap<String, bool>,
+    reruns: usize,
+    time_limit: Duration,
+    opts: &EvalOptions,
+) -> std::io::Result<Reruns> {
+    let mut counts: BTreeMap<String, usize> = results
+        .iter()
+        .map(|(name, &passed)| (name.clone(), passed as usize))
+        .collect();
+    let contention_cpus = contention_cpus(opts);
+    let mut contended_reruns = 0;
+    for i in 0..reruns {
+        let Schedule { threads, contended } = schedule(i, opts.cpus_per_job);
+        let _busy = match &contention_cpus {
+            Some(cpus) if contended => {
+                contended_reruns += 1;
+                Some(Contention::start(opts.cpus_per_job, cpus))
+            }
+            _ => None,
+        };
+        let mut passed: HashMap<String, bool> = HashMap::new();
+        let started = Instant::now();
+        for exe in binaries {
+            let remaining = time_limit.saturating_sub(started.elapsed());
+            if remaining.is_zero() {
+                break;
+            }
+            let output = run_test_binary_on(exe, remaining, threads, opts)?;
+            parse_test_output(&output, &mut passed);
+        }
+        for (name, count) in counts.iter_mut() {
+            *count += passed.get(name).copied().unwrap_or(false) as usize;
+        }
+    }
+
+    let flaky_tests: Vec<String> = counts
+        .iter()
+        .filter(|(_, &count)| count > 0 && count <= reruns)
+        .map(|(name, _)| name.clone())
+        .collect();
+    Ok(Reruns {
+        reruns,
+        contended_reruns,
+        flaky: !flaky_tests.is_empty(),
+        test_pass_counts: counts,
+        flaky_tests,
+    })
+}
+
+/// The CPUs busy threads may spin on without slowing other jobs: any with
+/// a single job, otherwise the job's own slice, which the worker thread is
+/// pinned to. `None` if the worker isn't pinned or shares CPUs with others.
+fn contention_cpus(opts: &EvalOptions) -> Option<Vec<usize>> {
+    if opts.jobs == 1 {
+        return Some(Vec::new());
+    }
+    let own = sandbox::allowed_cpus();
+    let exclusive = opts.jobs * opts.cpus_per_job <= opts.allowed_cpus.len();
+    (exclusive && !own.is_empty() && own.len() <= opts.cpus_per_job).then_some(own)
+}
+
+/// Threads spinning on `cpus` (anywhere if empty) until dropped.
+struct Contention {
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Contention {
+    fn start(count: usize, cpus: &[usize]) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let threads = (0..count)
+            .map(|_| {
+                let stop = Arc::clone(&stop);
+                let cpus = cpus.to_vec();
+                thread::spawn(move || {
+                    if !cpus.is_empty() && sandbox::pin_thread(&cpus).is_err() {
+                        return;
+                    }
+                    while !stop.load(Ordering::Relaxed) {
+                        std::hint::spin_loop();
+                    }
+                })
+            })
+            .collect();
+        Contention { stop, threads }
+    }
+}
+
+impl Drop for Contention {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for handle in self.threads.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
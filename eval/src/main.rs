@@ -1,11 +1,26 @@
+mod benchmark;
+mod clippy;
+mod constraints;
+mod extract;
+mod flaky;
+mod metrics;
+mod own_tests;
+mod placeholders;
+mod properties;
+mod reference;
+mod rustfmt;
+mod sandbox;
+
+use clap::Parser;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
-use std::process::Command;
-use std::time::Duration;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tempfile::TempDir;
 use walkdir::WalkDir;
 
@@ -14,6 +29,13 @@ use walkdir::WalkDir;
 #[derive(Debug, Deserialize)]
 struct TaskFile {
     task: Task,
+    /// SHA-256 of the raw TOML file, filled in after parsing.
+    #[serde(skip)]
+    definition_hash: String,
+    /// `src/lib.rs` of the reference solution, loaded for tasks with a
+    /// property test or a benchmark.
+    #[serde(skip)]
+    reference: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -30,6 +52,10 @@ struct Task {
     description: TaskDescription,
     constraints: Constraints,
     tests: Tests,
+    #[serde(default)]
+    properties: Option<PropertySpec>,
+    #[serde(default)]
+    benchmark: Option<BenchmarkSpec>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -56,6 +82,85 @@ struct Tests {
 struct TestCase {
     name: String,
     code: String,
+    #[serde(default)]
+    set: TestSet,
+}
+
+/// Whether prompts may show a test case (see the runner's `{{examples}}`).
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum TestSet {
+    Visible,
+    #[default]
+    Hidden,
+}
+
+/// A differential property test against the reference solution. Each
+/// snippet is Rust source spliced into the harness (see `properties`).
+#[derive(Debug, Deserialize)]
+struct PropertySpec {
+    /// Type of one input: `Clone + Debug` and shrinkable, as integers,
+    /// `bool`, `char`, `f64`, `String`, `Vec`, `Option` and tuples are.
+    input: String,
+    /// Expression making an input from `rng: &mut Rng` and `size: usize`,
+    /// which grows from 0 to `max_size` over the run.
+    generate: String,
+    /// Expression calling the solution, as `sol`, on `input: &Input`. Its
+    /// `{:?}` output is compared, so floats and the crates' own types should
+    /// be mapped to plain values.
+    call: String,
+    /// Boolean expression on `input: &Input` accepting only inputs the task
+    /// allows; shrinking stays within them.
+    #[serde(default)]
+    valid: Option<String>,
+    /// Items the other snippets use.
+    #[serde(default)]
+    helpers: String,
+    #[serde(default = "default_property_cases")]
+    cases: usize,
+    #[serde(default = "default_max_size")]
+    max_size: usize,
+    #[serde(default)]
+    seed: u64,
+}
+
+fn default_property_cases() -> usize {
+    200
+}
+
+fn default_max_size() -> usize {
+    32
+}
+
+/// A scaling benchmark: one workload timed at several sizes on the candidate
+/// and on the reference solution (see `benchmark`). Snippets are spliced into
+/// the harness as for `PropertySpec`.
+#[derive(Debug, Deserialize)]
+struct BenchmarkSpec {
+    /// Workload sizes, ascending.
+    sizes: Vec<usize>,
+    /// How the workload's time grows with a correct solution; without it,
+    /// the candidate is held to the reference's fitted growth.
+    #[serde(default)]
+    expected: Option<benchmark::Growth>,
+    /// Type of the prepared input.
+    input: String,
+    /// Expression preparing the input of size `n: usize` from `rng: &mut
+    /// Rng`; not timed.
+    setup: String,
+    /// The timed workload: statements calling the solution, as `sol`, on
+    /// `input: &Input` and `n`, ending in a value that is kept alive.
+    run: String,
+    /// Items the other snippets use.
+    #[serde(default)]
+    helpers: String,
+    /// Timings per size and side; the best is kept.
+    #[serde(default = "default_repeats")]
+    repeats: usize,
+}
+
+fn default_repeats() -> usize {
+    3
 }
 
 // ── Meta JSON schema ────────────────────────────────────────────────
@@ -74,115 +179,445 @@ struct Meta {
     input_tokens: u64,
     #[serde(default)]
     output_tokens: u64,
+    /// Why generation stopped (absent in older results).
+    #[serde(default)]
+    finish_reason: Option<String>,
+    /// Provenance hashes recorded by the runner (absent in older results).
+    #[serde(default)]
+    task_hash: Option<String>,
+    #[serde(default)]
+    tests_hash: Option<String>,
 }
 
 // ── Output schema ───────────────────────────────────────────────────
 
 #[derive(Debug, Serialize)]
 struct EvalResult {
+    experiment_id: String,
+    model: String,
     task_id: String,
     style: String,
     run: u32,
+    /// The solution library compiled (even if some tests did not).
     compiled: bool,
     tests_total: usize,
     tests_passed: usize,
     tests_failed: Vec<String>,
-    loc: usize,
-    unsafe_count: usize,
-    unwrap_count: usize,
-    expect_count: usize,
+    /// Panic details for tests that ran and failed, in test order.
+    failures: Vec<TestFailure>,
+    /// Tests in the task's visible set, which prompts may show, and in its
+    /// hidden set.
+    visible_total: usize,
+    visible_passed: usize,
+    hidden_total: usize,
+    hidden_passed: usize,
+    /// Visible minus hidden pass rate, if the task has both sets. A large
+    /// gap points at code fitted to the shown examples.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    visibility_gap: Option<f64>,
+    outcome: Outcome,
+    /// What went wrong when `outcome` is `infra_error`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    infra_error: Option<String>,
+    /// Signal that killed a test binary, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    crash_signal: Option<i32>,
+    /// Tests that failed to compile although the library compiled; they are
+    /// also listed in `tests_failed`.
+    tests_compile_failed: Vec<String>,
+    /// The combined test build failed and each test was built separately.
+    isolated: bool,
+    /// Error codes of all compiler errors, one per error, in emission order.
+    error_codes: Vec<String>,
+    error_count: usize,
+    warning_count: usize,
+    diagnostics: Vec<Diagnostic>,
+    compile_timed_out: bool,
+    test_timed_out: bool,
+    #[serde(flatten)]
+    metrics: metrics::Metrics,
+    /// Placeholder code: `todo!()`, elided code, stubbed functions.
+    #[serde(flatten)]
+    placeholders: placeholders::Placeholders,
+    /// Clippy lint counts for compiled libraries.
+    #[serde(flatten)]
+    lints: clippy::Lints,
+    /// The response's own unit tests and doctests.
+    #[serde(flatten)]
+    own_tests: own_tests::OwnTests,
+    /// Differential property test against the reference solution.
+    #[serde(flatten)]
+    differential: properties::Differential,
+    /// Scaling benchmark against the reference solution.
+    #[serde(flatten)]
+    benchmark: benchmark::Benchmark,
+    /// Pass counts over reruns of the test binaries.
+    #[serde(flatten)]
+    reruns: flaky::Reruns,
+    #[serde(flatten)]
+    formatting: rustfmt::Formatting,
+    /// Every `must_use` item appears as a path in non-test code.
     must_use_satisfied: bool,
+    /// First use of each `must_use` item found.
+    must_use_evidence: Vec<constraints::Evidence>,
+    /// A forbidden item appears as a path in non-test code (`unsafe`: as
+    /// unsafe code).
     forbidden_violated: bool,
+    forbidden_evidence: Vec<constraints::Evidence>,
+    /// How the code was extracted from the response.
+    extraction: extract::Strategy,
     had_markdown_fences: bool,
+    response_kind: ResponseKind,
+    /// Prose outside the code: non-blank lines and their characters.
+    prose_lines: usize,
+    prose_chars: usize,
+    /// The task definition changed since the response was generated.
+    task_drift: bool,
+    /// The test cases changed since the response was generated.
+    tests_drift: bool,
+    /// Wall-clock time of the build and of the test binary.
+    compile_ms: u64,
+    test_ms: u64,
     latency_ms: u64,
     input_tokens: u64,
     output_tokens: u64,
 }
 
+/// One rustc diagnostic from cargo's JSON message stream.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Diagnostic {
+    level: String,
+    /// Error code such as `E0502`, or a lint name for lint warnings.
+    code: Option<String>,
+    message: String,
+    /// Primary span, relative to the temp crate (`src/lib.rs` or
+    /// `tests/integration.rs`).
+    span: Option<Span>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Span {
+    file: String,
+    line: usize,
+    column: usize,
+}
+
+/// Why one test failed, from its section of libtest's `failures:` output.
+/// Long fields are capped (see `cap`).
+#[derive(Debug, Clone, Serialize)]
+struct TestFailure {
+    test: String,
+    /// Panic message without the `left`/`right` lines.
+    message: Option<String>,
+    /// `assert_eq!`/`assert_ne!` operands, as printed with `{:?}`.
+    left: Option<String>,
+    right: Option<String>,
+    /// Where the panic happened: `file:line:column` in the temp crate.
+    location: Option<String>,
+    /// Everything the test printed, panic included.
+    output: String,
+}
+
+/// Why a response got the score it did. Exactly one class applies, checked
+/// in declaration order; `infra_error` results say nothing about the
+/// response and are left out of style comparisons.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Outcome {
+    /// The harness failed: temp dir, cargo spawn or compile budget.
+    InfraError,
+    /// No code, and the prose declines the task.
+    Refusal,
+    /// Generation was cut off before the code was complete.
+    Truncated,
+    /// Empty, or not Rust source at all.
+    Unparseable,
+    LibCompileError,
+    /// A test binary was killed by a signal (stack overflow, segfault, …).
+    Crash,
+    /// A test binary exceeded its time or CPU limit.
+    Timeout,
+    /// The library compiled but some tests did not.
+    TestCompileError,
+    /// At least one test failed.
+    TestPanic,
+    Passed,
+}
+
+/// What a response is, judged from its text before anything is compiled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ResponseKind {
+    /// Code and nothing else (comments aside).
+    Code,
+    /// Code with explanation around it, against the code-only system prompt.
+    CodeWithProse,
+    /// An explanation or discussion without code.
+    ProseOnly,
+    /// No code, and the prose asks something back.
+    ClarifyingQuestion,
+    Refusal,
+    Empty,
+}
+
 // ── Helpers ─────────────────────────────────────────────────────────
 
-/// Strip markdown code fences (```rust / ```) that LLMs sometimes emit.
-/// Returns (cleaned_code, had_fences).
-fn strip_markdown_fences(raw: &str) -> (String, bool) {
-    let mut inside_lines: Vec<&str> = Vec::new();
-    let mut outside_lines: Vec<&str> = Vec::new();
-    let mut inside_fence = false;
-    let mut fence_count: usize = 0;
-
-    for line in raw.lines() {
-        let trimmed = line.trim();
-        if trimmed.starts_with("```") {
-            fence_count += 1;
-            inside_fence = !inside_fence;
-            continue;
+fn sha256_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Hash the test cases independently of the rest of the task file.
+/// Must stay in sync with `hash_test_cases` in the runner.
+fn hash_test_cases(cases: &[TestCase]) -> String {
+    let mut buf = Vec::new();
+    for tc in cases {
+        buf.extend_from_slice(tc.name.as_bytes());
+        buf.push(0);
+        buf.extend_from_slice(tc.code.as_bytes());
+        buf.push(0);
+        if tc.set == TestSet::Visible {
+            buf.extend_from_slice(b"visible");
+            buf.push(0);
         }
-        if inside_fence {
-            inside_lines.push(line);
+    }
+    sha256_hex(&buf)
+}
+
+/// Assign the outcome class for a response; see `Outcome`.
+fn classify(
+    run: &TestRun,
+    raw: &str,
+    code: &str,
+    finish_reason: Option<&str>,
+    all_passed: bool,
+) -> Outcome {
+    if run.infra_error.is_some() {
+        return Outcome::InfraError;
+    }
+    let parsed = syn::parse_file(code);
+    let has_items = parsed.as_ref().is_ok_and(|file| !file.items.is_empty());
+    if !has_items && is_refusal(raw) {
+        return Outcome::Refusal;
+    }
+    // An empty library compiles, so a response without items is judged here
+    // rather than by its test outcome.
+    if !has_items || !run.compiled {
+        let cut_off = finish_reason == Some("length")
+            || (finish_reason.is_none() && !has_items && looks_cut_off(raw, code));
+        return if cut_off {
+            Outcome::Truncated
+        } else if !has_items {
+            Outcome::Unparseable
         } else {
-            outside_lines.push(line);
-        }
+            Outcome::LibCompileError
+        };
+    }
+    if run.crash_signal.is_some() {
+        Outcome::Crash
+    } else if run.test_timed_out {
+        Outcome::Timeout
+    } else if !run.tests_compile_failed.is_empty() {
+        Outcome::TestCompileError
+    } else if !all_passed {
+        Outcome::TestPanic
+    } else {
+        Outcome::Passed
     }
+}
 
-    if fence_count >= 2 && !inside_lines.is_empty() {
-        // Fences were detected – only keep content from inside the fences.
-        (inside_lines.join("\n"), true)
+fn response_kind(raw: &str, extraction: &extract::Extraction) -> ResponseKind {
+    if raw.trim().is_empty() {
+        ResponseKind::Empty
+    } else if extraction.has_code && extraction.prose_lines == 0 {
+        ResponseKind::Code
+    } else if extraction.has_code {
+        ResponseKind::CodeWithProse
+    } else if is_refusal(raw) {
+        ResponseKind::Refusal
+    } else if raw.lines().any(|l| l.trim_end().ends_with('?')) {
+        ResponseKind::ClarifyingQuestion
     } else {
-        // No fences (or a single stray fence) – keep everything outside.
-        (outside_lines.join("\n"), fence_count > 0)
+        ResponseKind::ProseOnly
     }
 }
 
-/// Count logical lines of code (non-blank, non-comment-only).
-fn count_loc(code: &str) -> usize {
-    code.lines()
-        .filter(|l| {
-            let t = l.trim();
-            !t.is_empty() && !t.starts_with("//")
-        })
-        .count()
+/// Phrases models use to decline a task.
+fn is_refusal(raw: &str) -> bool {
+    let re = Regex::new(
+        r"(?i)\b(I can(no|'|’)t|I'm (sorry|unable)|I am (sorry|unable)|I won'?t|as an AI)\b",
+    )
+    .expect("bad regex");
+    re.is_match(raw)
 }
 
-/// Count occurrences of a literal pattern in code.
-fn count_pattern(code: &str, pattern: &str) -> usize {
-    code.matches(pattern).count()
+/// Without a finish reason from the API, an unclosed code fence or more
+/// opening than closing braces suggests the response was cut off.
+fn looks_cut_off(raw: &str, code: &str) -> bool {
+    raw.matches("```").count() % 2 == 1 || code.matches('{').count() > code.matches('}').count()
 }
 
-/// Count `unsafe {` or `unsafe{` blocks.
-fn count_unsafe(code: &str) -> usize {
-    let re = Regex::new(r"unsafe\s*\{").expect("bad regex");
-    re.find_iter(code).count()
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::InfraError => "infra_error",
+            Outcome::Refusal => "refusal",
+            Outcome::Truncated => "truncated",
+            Outcome::Unparseable => "unparseable",
+            Outcome::LibCompileError => "lib_compile_error",
+            Outcome::Crash => "crash",
+            Outcome::Timeout => "timeout",
+            Outcome::TestCompileError => "test_compile_error",
+            Outcome::TestPanic => "test_panic",
+            Outcome::Passed => "passed",
+        }
+    }
 }
 
-/// Build a temporary crate, run `cargo test --release`, return (compiled, test_results).
+/// Console output of one evaluation, held back and printed as one block so
+/// that parallel jobs don't interleave.
+struct JobLog {
+    lines: Vec<(bool, String)>,
+}
+
+static CONSOLE: Mutex<()> = Mutex::new(());
+
+impl JobLog {
+    fn new(header: String) -> Self {
+        JobLog {
+            lines: vec![(false, header)],
+        }
+    }
+
+    fn out(&mut self, line: String) {
+        self.lines.push((false, line));
+    }
+
+    fn err(&mut self, line: String) {
+        self.lines.push((true, line));
+    }
+
+    fn flush(self) {
+        let _guard = CONSOLE.lock();
+        for (is_err, line) in self.lines {
+            if is_err {
+                eprintln!("{line}");
+            } else {
+                println!("{line}");
+            }
+        }
+    }
+}
+
+/// Outcome of building and running one candidate's test suite.
+struct TestRun {
+    /// The solution library compiled.
+    compiled: bool,
+    /// The combined test build failed, so each test was built separately.
+    isolated: bool,
+    /// Tests that failed to compile against an otherwise compiling library.
+    tests_compile_failed: Vec<String>,
+    /// The build exceeded `--compile-timeout`.
+    compile_timed_out: bool,
+    /// A test binary exceeded the task's `time_limit_seconds`.
+    test_timed_out: bool,
+    compile_ms: u64,
+    test_ms: u64,
+    results: HashMap<String, bool>,
+    failures: HashMap<String, TestFailure>,
+    diagnostics: Vec<Diagnostic>,
+    /// The harness itself failed, so nothing can be said about the response.
+    infra_error: Option<String>,
+    /// Signal that killed a test binary, other than our own time-out kill.
+    crash_signal: Option<i32>,
+    lints: clippy::Lints,
+    own_tests: own_tests::OwnTests,
+    differential: properties::Differential,
+    benchmark: benchmark::Benchmark,
+    reruns: flaky::Reruns,
+}
+
+impl TestRun {
+    /// A run in which nothing compiled and every test failed.
+    fn failed(test_cases: &[TestCase]) -> Self {
+        TestRun {
+            compiled: false,
+            isolated: false,
+            tests_compile_failed: Vec::new(),
+            compile_timed_out: false,
+            test_timed_out: false,
+            compile_ms: 0,
+            test_ms: 0,
+            results: test_cases.iter().map(|tc| (tc.name.clone(), false)).collect(),
+            failures: HashMap::new(),
+            diagnostics: Vec::new(),
+            infra_error: None,
+            crash_signal: None,
+            lints: clippy::Lints::default(),
+            own_tests: own_tests::OwnTests::default(),
+            differential: properties::Differential::default(),
+            benchmark: benchmark::Benchmark::default(),
+            reruns: flaky::Reruns::default(),
+        }
+    }
+
+    /// Record a harness failure and echo it to the job log.
+    fn infra_error(mut self, log: &mut JobLog, message: String) -> Self {
+        log.err(format!("  ✗ {message}"));
+        self.infra_error = Some(message);
+        self
+    }
+
+    fn add_diagnostics(&mut self, diagnostics: impl IntoIterator<Item = Diagnostic>) {
+        for diag in diagnostics {
+            // Cargo replays a fresh library's warnings on every build.
+            if !self.diagnostics.contains(&diag) {
+                self.diagnostics.push(diag);
+            }
+        }
+    }
+}
+
+/// Edition of the temp crate each candidate is built in; rustfmt uses it too.
+const EDITION: &str = "2021";
+
+/// Build a temporary crate's tests in release mode under the compile budget,
+/// then run the test binaries under the task's time limit, so that only test
+/// execution counts against it.
+///
+/// All cases are first built into one `tests/integration.rs`. If that fails
+/// while the library itself compiles, each case is rebuilt as its own test
+/// target, so one test naming a missing method doesn't fail the rest.
 fn run_tests(
     solution_code: &str,
-    test_cases: &[TestCase],
-    time_limit: Duration,
-) -> (bool, HashMap<String, bool>) {
+    task_def: &TaskFile,
+    opts: &EvalOptions,
+    target_dir: &Path,
+    log: &mut JobLog,
+) -> TestRun {
+    let test_cases = &task_def.task.tests.cases;
+    let time_limit = Duration::from_secs(task_def.task.constraints.time_limit_seconds);
+    let mut run = TestRun::failed(test_cases);
+    run.own_tests = own_tests::count(solution_code);
+
     let tmp = match TempDir::new() {
         Ok(d) => d,
-        Err(e) => {
-            eprintln!("  ✗ could not create temp dir: {e}");
-            let map: HashMap<String, bool> =
-                test_cases.iter().map(|tc| (tc.name.clone(), false)).collect();
-            return (false, map);
-        }
+        Err(e) => return run.infra_error(log, format!("could not create temp dir: {e}")),
     };
 
     let crate_dir = tmp.path();
 
     // Cargo.toml
-    let cargo_toml = r#"[package]
-name = "solution"
-version = "0.1.0"
-edition = "2021"
-"#;
+    let cargo_toml = format!(
+        "[package]\nname = \"solution\"\nversion = \"0.1.0\"\nedition = \"{EDITION}\"\n"
+    );
     let src_dir = crate_dir.join("src");
     let tests_dir = crate_dir.join("tests");
     if fs::create_dir_all(&src_dir).is_err() || fs::create_dir_all(&tests_dir).is_err() {
-        let map: HashMap<String, bool> =
-            test_cases.iter().map(|tc| (tc.name.clone(), false)).collect();
-        return (false, map);
+        return run.infra_error(log, "could not create temp crate".to_string());
     }
 
     let write = |p: PathBuf, content: &str| -> bool {
@@ -192,177 +627,914 @@ edition = "2021"
         }
     };
 
-    if !write(crate_dir.join("Cargo.toml"), cargo_toml) {
-        let map: HashMap<String, bool> =
-            test_cases.iter().map(|tc| (tc.name.clone(), false)).collect();
-        return (false, map);
-    }
-    if !write(src_dir.join("lib.rs"), solution_code) {
-        let map: HashMap<String, bool> =
-            test_cases.iter().map(|tc| (tc.name.clone(), false)).collect();
-        return (false, map);
+    if !write(crate_dir.join("Cargo.toml"), &cargo_toml)
+        || !write(src_dir.join("lib.rs"), solution_code)
+    {
+        return run.infra_error(log, "could not write temp crate".to_string());
     }
 
     // Build integration test file
     let mut integration = String::from("use solution::*;\n\n");
     for tc in test_cases {
-        integration.push_str(&format!("#[test]\nfn {}() {{\n{}\n}}\n\n", tc.name, tc.code));
+        integration.push_str(&test_fn(tc));
     }
     if !write(tests_dir.join("integration.rs"), &integration) {
-        let map: HashMap<String, bool> =
-            test_cases.iter().map(|tc| (tc.name.clone(), false)).collect();
-        return (false, map);
+        return run.infra_error(log, "could not write temp crate".to_string());
     }
 
-    // Run cargo test --release with timeout
-    let child = Command::new("cargo")
-        .args(["test", "--release"])
-        .current_dir(crate_dir)
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn();
-
-    let mut child = match child {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("  ✗ failed to spawn cargo: {e}");
-            let map: HashMap<String, bool> =
-                test_cases.iter().map(|tc| (tc.name.clone(), false)).collect();
-            return (false, map);
-        }
+    let started = Instant::now();
+    let build = cargo_build(
+        crate_dir,
+        &["integration"],
+        opts.compile_timeout,
+        opts,
+        target_dir,
+    );
+    let build = match build {
+        Ok(b) => b,
+        Err(e) => return run.infra_error(log, format!("failed to spawn cargo: {e}")),
     };
+    run.compile_ms = started.elapsed().as_millis() as u64;
+    let mut artifacts = build.artifacts.clone();
 
-    // Wait with timeout
-    let status = wait_with_timeout(&mut child, time_limit);
-
-    let (compiled, stdout_str, stderr_str) = match status {
-        Some(output) => {
-            let stdout_s = String::from_utf8_lossy(&output.0).to_string();
-            let stderr_s = String::from_utf8_lossy(&output.1).to_string();
-            let compiled = !stderr_s.contains("could not compile")
-                && !stderr_s.contains("error[E");
-            (compiled, stdout_s, stderr_s)
-        }
-        None => {
-            // Timeout – kill
-            let _ = child.kill();
-            let _ = child.wait();
-            eprintln!("  ✗ timeout");
-            let map: HashMap<String, bool> =
-                test_cases.iter().map(|tc| (tc.name.clone(), false)).collect();
-            return (false, map);
+    if build.timed_out {
+        // A tiny crate that can't build within the budget points at a
+        // starved machine rather than at the response.
+        run.compile_timed_out = true;
+        remove_artifacts(&artifacts);
+        let message = format!("compile timeout ({}s)", opts.compile_timeout.as_secs());
+        return run.infra_error(log, message);
+    }
+
+    let mut binaries: Vec<PathBuf> = Vec::new();
+    if let Some(exe) = build.executables.get("integration").filter(|_| build.success) {
+        run.add_diagnostics(build.diagnostics);
+        binaries.push(exe.clone());
+    } else if !build.lib_built {
+        log.err("  ✗ compilation failed".to_string());
+        log_build_errors(log, &build.diagnostics, &build.stderr);
+        run.add_diagnostics(build.diagnostics);
+        remove_artifacts(&artifacts);
+        return run;
+    } else {
+        // The library compiles but some test doesn't: build each on its own,
+        // within what is left of the compile budget.
+        log.err("  ⚠ combined test build failed; building each test separately".to_string());
+        run.isolated = true;
+        let mut names: Vec<&str> = Vec::new();
+        for tc in test_cases {
+            let source = format!("use solution::*;\n\n{}", test_fn(tc));
+            if !write(tests_dir.join(format!("{}.rs", tc.name)), &source) {
+                remove_artifacts(&artifacts);
+                return run.infra_error(log, "could not write temp crate".to_string());
+            }
+            names.push(&tc.name);
+        }
+        let remaining = opts.compile_timeout.saturating_sub(started.elapsed());
+        let isolated = match cargo_build(crate_dir, &names, remaining, opts, target_dir) {
+            Ok(b) => b,
+            Err(e) => {
+                remove_artifacts(&artifacts);
+                return run.infra_error(log, format!("failed to spawn cargo: {e}"));
+            }
+        };
+        run.compile_ms = started.elapsed().as_millis() as u64;
+        artifacts.extend(isolated.artifacts.iter().cloned());
+        if isolated.timed_out {
+            run.compile_timed_out = true;
+            remove_artifacts(&artifacts);
+            let message = format!("compile timeout ({}s)", opts.compile_timeout.as_secs());
+            return run.infra_error(log, message);
+        }
+
+        // Keep the library's diagnostics from the combined build and take the
+        // tests' from the isolated one.
+        run.add_diagnostics(build.diagnostics.into_iter().filter(|d| {
+            d.span.as_ref().is_none_or(|sp| !sp.file.starts_with("tests/"))
+        }));
+        log_build_errors(log, &isolated.diagnostics, "");
+        run.add_diagnostics(isolated.diagnostics);
+        for tc in test_cases {
+            match isolated.executables.get(&tc.name) {
+                Some(exe) => binaries.push(exe.clone()),
+                None => run.tests_compile_failed.push(tc.name.clone()),
+            }
+        }
+        if !run.tests_compile_failed.is_empty() {
+            log.err(format!(
+                "  ✗ {} test(s) failed to compile: {}",
+                run.tests_compile_failed.len(),
+                run.tests_compile_failed.join(", ")
+            ));
+        }
+    }
+    run.compiled = true;
+
+    // Run only the test binaries against the task's time limit, confined to
+    // their own scratch dir. CPU time may accrue on every test thread.
+    let started = Instant::now();
+    for exe in &binaries {
+        let remaining = time_limit.saturating_sub(started.elapsed());
+        if remaining.is_zero() {
+            run.test_timed_out = true;
+            break;
+        }
+        let output = match run_test_binary(exe, remaining, opts) {
+            Ok(o) => o,
+            Err(e) => {
+                remove_artifacts(&artifacts);
+                return run.infra_error(log, format!("failed to run test binary: {e}"));
+            }
+        };
+        match output.signal {
+            _ if output.timed_out => run.test_timed_out = true,
+            Some(sig) if sandbox::is_cpu_limit(sig) => {
+                log.err("  ✗ test binary ran out of CPU time".to_string());
+                run.test_timed_out = true;
+            }
+            Some(sig) => {
+                log.err(format!("  ✗ test binary killed by signal {sig}"));
+                run.crash_signal = Some(sig);
+            }
+            None => {}
+        }
+        parse_test_output(&output, &mut run.results);
+        run.failures.extend(parse_failures(&String::from_utf8_lossy(&output.stdout)));
+    }
+    run.test_ms = started.elapsed().as_millis() as u64;
+
+    // Rerun the same binaries under other schedules; the score keeps the
+    // first run.
+    if opts.reruns > 0 {
+        match flaky::rerun(&binaries, &run.results, opts.reruns, time_limit, opts) {
+            Ok(reruns) => {
+                log_reruns(log, &reruns);
+                run.reruns = reruns;
+            }
+            Err(e) => log.err(format!("  ⚠ could not rerun the tests: {e}")),
+        }
+    }
+
+    // The candidate's own tests and doc examples, apart from the score.
+    if run.own_tests.wrote_tests {
+        match own_tests::run(&mut run.own_tests, crate_dir, time_limit, opts, target_dir) {
+            Ok(own_artifacts) => artifacts.extend(own_artifacts),
+            Err(e) => log.err(format!("  ⚠ could not run the response's own tests: {e}")),
+        }
+    }
+
+    // Run beside the reference solution, also apart from the score.
+    if let Some(code) = &task_def.reference {
+        if let Err(e) = reference::add(crate_dir, code) {
+            log.err(format!("  ⚠ could not add the reference solution: {e}"));
+        } else {
+            if let Some(spec) = &task_def.task.properties {
+                match properties::run(spec, crate_dir, time_limit, opts, target_dir) {
+                    Ok((differential, property_artifacts)) => {
+                        log_differential(log, &differential, time_limit);
+                        run.differential = differential;
+                        artifacts.extend(property_artifacts);
+                    }
+                    Err(e) => log.err(format!("  ⚠ could not run the property test: {e}")),
+                }
+            }
+            if let Some(spec) = &task_def.task.benchmark {
+                match benchmark::run(spec, crate_dir, opts.bench_timeout, opts, target_dir) {
+                    Ok((bench, bench_artifacts)) => {
+                        log_benchmark(log, &bench, opts.bench_timeout);
+                        run.benchmark = bench;
+                        artifacts.extend(bench_artifacts);
+                    }
+                    Err(e) => log.err(format!("  ⚠ could not run the benchmark: {e}")),
+                }
+            }
         }
+    }
+
+    // Lint the library under a budget of its own; tests are already scored.
+    if opts.clippy {
+        match clippy::lint(crate_dir, opts.compile_timeout, opts, target_dir) {
+            Ok((lints, clippy_artifacts)) => {
+                if !lints.clippy_ran {
+                    log.err("  ⚠ clippy failed or timed out".to_string());
+                }
+                run.lints = lints;
+                artifacts.extend(clippy_artifacts);
+            }
+            Err(e) => log.err(format!("  ⚠ failed to spawn cargo clippy: {e}")),
+        }
+    }
+    remove_artifacts(&artifacts);
+
+    if run.test_timed_out {
+        // Tests that reported before the limit still count.
+        log.err(format!("  ✗ test timeout ({}s)", time_limit.as_secs()));
+    }
+
+    run
+}
+
+/// Echo a property test that didn't agree with the reference.
+fn log_differential(log: &mut JobLog, differential: &properties::Differential, limit: Duration) {
+    use properties::PropertyStatus;
+    match differential.property_status {
+        PropertyStatus::Diverged => {
+            if let Some(c) = &differential.property_counterexample {
+                log.err(format!(
+                    "  ⚠ differs from the reference on {}: expected {}, got {}",
+                    cap(&c.shrunk_input, 200),
+                    cap(&c.expected, 200),
+                    cap(&c.actual, 200)
+                ));
+            }
+        }
+        PropertyStatus::CompileError => {
+            log.err("  ⚠ property test did not compile against the response".to_string());
+        }
+        PropertyStatus::Timeout => {
+            log.err(format!("  ⚠ property test timeout ({}s)", limit.as_secs()));
+        }
+        PropertyStatus::Crash => log.err("  ⚠ property test died without a report".to_string()),
+        PropertyStatus::NotRun => log.err("  ⚠ property test build timed out".to_string()),
+        PropertyStatus::Agreed => {}
+    }
+}
+
+/// Echo a benchmark that flagged the response or didn't finish.
+fn log_benchmark(log: &mut JobLog, bench: &benchmark::Benchmark, limit: Duration) {
+    use benchmark::BenchStatus;
+    match bench.bench_status {
+        BenchStatus::CompileError => {
+            log.err("  ⚠ benchmark did not compile against the response".to_string());
+        }
+        BenchStatus::Timeout => log.err(format!("  ⚠ benchmark timeout ({}s)", limit.as_secs())),
+        BenchStatus::Crash => log.err("  ⚠ benchmark died before finishing".to_string()),
+        BenchStatus::NotRun => log.err("  ⚠ benchmark build timed out".to_string()),
+        BenchStatus::Measured => {}
+    }
+    if bench.bench_flagged {
+        let growth = bench
+            .bench_exponent
+            .map(|k| format!("time grows as n^{k:.2}"))
+            .unwrap_or_else(|| "too few sizes finished".to_string());
+        let ratio = bench
+            .bench_time_ratio
+            .map(|r| format!(", {r:.1}× the reference's time"))
+            .unwrap_or_default();
+        log.err(format!("  ⚠ asymptotically slower than expected: {growth}{ratio}"));
+    }
+}
+
+/// Echo the tests that passed in some runs but not all.
+fn log_reruns(log: &mut JobLog, reruns: &flaky::Reruns) {
+    if reruns.flaky {
+        let tests: Vec<String> = reruns
+            .flaky_tests
+            .iter()
+            .map(|name| format!("{name} ({}/{})", reruns.test_pass_counts[name], reruns.reruns + 1))
+            .collect();
+        log.err(format!("  ⚠ flaky: {}", tests.join(", ")));
+    }
+}
+
+/// One test case as a `#[test]` function.
+fn test_fn(tc: &TestCase) -> String {
+    format!("#[test]\nfn {}() {{\n{}\n}}\n\n", tc.name, tc.code)
+}
+
+/// What one build of the candidate's test targets produced.
+struct Build {
+    success: bool,
+    timed_out: bool,
+    /// The solution library itself compiled.
+    lib_built: bool,
+    diagnostics: Vec<Diagnostic>,
+    /// Test binaries by target name.
+    executables: HashMap<String, PathBuf>,
+    /// Every file the build wrote to the shared target dir.
+    artifacts: Vec<PathBuf>,
+    stderr: String,
+}
+
+/// Compile the given test targets with `cargo build --test`, continuing past
+/// targets that fail. Stdout carries JSON messages: diagnostics and the
+/// artifacts, which name the test binaries.
+fn cargo_build(
+    crate_dir: &Path,
+    test_targets: &[&str],
+    timeout: Duration,
+    opts: &EvalOptions,
+    target_dir: &Path,
+) -> std::io::Result<Build> {
+    let mut cmd = Command::new("cargo");
+    sandbox::own_process_group(&mut cmd)
+        .args([
+            "build",
+            "--release",
+            "--offline",
+            "--message-format=json",
+            "--keep-going",
+        ])
+        .arg(format!("--jobs={}", opts.cpus_per_job));
+    for target in test_targets {
+        cmd.args(["--test", target]);
+    }
+    let mut child = cmd
+        .env("CARGO_TARGET_DIR", target_dir)
+        .current_dir(crate_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let output = wait_with_timeout(&mut child, timeout);
+
+    let mut build = Build {
+        success: output.success,
+        timed_out: output.timed_out,
+        lib_built: false,
+        diagnostics: Vec::new(),
+        executables: HashMap::new(),
+        artifacts: Vec::new(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
     };
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Ok(msg) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if msg["reason"] == "compiler-message" {
+            if let Some(diag) = parse_diagnostic(&msg["message"]) {
+                build.diagnostics.push(diag);
+            }
+            continue;
+        }
+        if msg["reason"] != "compiler-artifact" {
+            continue;
+        }
+        if let Some(files) = msg["filenames"].as_array() {
+            build
+                .artifacts
+                .extend(files.iter().filter_map(|f| f.as_str()).map(PathBuf::from));
+        }
+        let is_lib = msg["target"]["kind"]
+            .as_array()
+            .is_some_and(|kinds| kinds.iter().any(|k| k == "lib"));
+        if is_lib {
+            build.lib_built = true;
+        } else if let (Some(name), Some(exe)) =
+            (msg["target"]["name"].as_str(), msg["executable"].as_str())
+        {
+            build.executables.insert(name.to_string(), PathBuf::from(exe));
+        }
+    }
+    Ok(build)
+}
 
-    if !compiled {
-        eprintln!("  ✗ compilation failed");
-        let combined = format!("{stdout_str}\n{stderr_str}");
-        for line in combined.lines().filter(|l| l.contains("error")) {
-            eprintln!("    {line}");
+/// Echo a failed build's errors to the console.
+fn log_build_errors(log: &mut JobLog, diagnostics: &[Diagnostic], stderr: &str) {
+    for diag in diagnostics.iter().filter(|d| d.level == "error") {
+        let code = diag.code.as_deref().map(|c| format!("[{c}]")).unwrap_or_default();
+        let at = diag
+            .span
+            .as_ref()
+            .map(|sp| format!(" at {}:{}:{}", sp.file, sp.line, sp.column))
+            .unwrap_or_default();
+        log.err(format!("    error{code}: {}{at}", diag.message));
+    }
+    if diagnostics.iter().all(|d| d.level != "error") {
+        // Not a rustc error (e.g. a cargo failure); show what cargo said.
+        for line in stderr.lines().filter(|l| l.contains("error")) {
+            log.err(format!("    {line}"));
         }
-        let map: HashMap<String, bool> =
-            test_cases.iter().map(|tc| (tc.name.clone(), false)).collect();
-        return (false, map);
     }
+}
 
-    // Parse test results from stdout
-    let mut results: HashMap<String, bool> = HashMap::new();
-    let combined = format!("{stdout_str}\n{stderr_str}");
+/// Run one test binary under `time_limit`, confined to a scratch dir.
+fn run_test_binary(
+    exe: &Path,
+    time_limit: Duration,
+    opts: &EvalOptions,
+) -> std::io::Result<ProcessOutput> {
+    run_test_binary_on(exe, time_limit, opts.cpus_per_job, opts)
+}
+
+/// `run_test_binary` with libtest running `threads` tests at a time.
+fn run_test_binary_on(
+    exe: &Path,
+    time_limit: Duration,
+    threads: usize,
+    opts: &EvalOptions,
+) -> std::io::Result<ProcessOutput> {
+    let scratch = TempDir::new()?;
+    // CPU time may accrue on every test thread.
+    let limits = sandbox::Limits {
+        cpu: time_limit * opts.cpus_per_job as u32 + Duration::from_secs(1),
+        ..opts.limits
+    };
+    let mut child = sandbox::confine(&mut Command::new(exe), limits, scratch.path())
+        .env("RUST_TEST_THREADS", threads.to_string())
+        .env("RUST_BACKTRACE", "0")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    Ok(wait_with_timeout(&mut child, time_limit))
+}
+
+/// Record the `test NAME ... ok|FAILED` lines from libtest's output.
+fn parse_test_output(output: &ProcessOutput, results: &mut HashMap<String, bool>) {
+    let combined = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
     let re = Regex::new(r"test (\S+)\s+\.\.\.\s+(ok|FAILED)").expect("bad regex");
     for cap in re.captures_iter(&combined) {
         let name = cap[1].to_string();
         let passed = &cap[2] == "ok";
         results.insert(name, passed);
     }
+}
 
-    // Any test not found in output is treated as failed
-    for tc in test_cases {
-        results.entry(tc.name.clone()).or_insert(false);
+const MAX_MESSAGE_BYTES: usize = 2048;
+const MAX_VALUE_BYTES: usize = 1024;
+const MAX_OUTPUT_BYTES: usize = 4096;
+
+/// Split libtest's `---- NAME stdout ----` sections into per-test failures.
+fn parse_failures(stdout: &str) -> HashMap<String, TestFailure> {
+    let header = Regex::new(r"^---- (\S+) stdout ----$").expect("bad regex");
+    let mut sections: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in stdout.lines() {
+        if let Some(cap) = header.captures(line) {
+            sections.push((cap[1].to_string(), Vec::new()));
+        } else if !sections.is_empty()
+            && (line == "failures:" || line.starts_with("test result:"))
+        {
+            // The trailing list of failed names ends the last section.
+            break;
+        } else if let Some((_, lines)) = sections.last_mut() {
+            lines.push(line);
+        }
     }
 
-    (true, results)
+    // Newer toolchains print the thread id after the name.
+    let panic =
+        Regex::new(r"^thread '.*'(?: \(\d+\))? panicked at (.+:\d+:\d+):$").expect("bad regex");
+    sections
+        .into_iter()
+        .map(|(test, lines)| {
+            let output = lines.join("\n").trim().to_string();
+            let mut location = None;
+            let mut message: Vec<&str> = Vec::new();
+            let (mut left, mut right) = (None, None);
+            let mut in_panic = false;
+            for line in &lines {
+                if let Some(cap) = panic.captures(line) {
+                    location = Some(cap[1].to_string());
+                    in_panic = true;
+                } else if let Some(v) = line.strip_prefix("  left: ") {
+                    left = Some(cap(v, MAX_VALUE_BYTES));
+                } else if let Some(v) = line.strip_prefix(" right: ") {
+                    right = Some(cap(v, MAX_VALUE_BYTES));
+                } else if line.starts_with("note: ") || *line == "stack backtrace:" {
+                    in_panic = false;
+                } else if in_panic && left.is_none() {
+                    message.push(line);
+                }
+            }
+            let message =
+                (!message.is_empty()).then(|| cap(&message.join("\n"), MAX_MESSAGE_BYTES));
+            let failure = TestFailure {
+                test: test.clone(),
+                message,
+                left,
+                right,
+                location,
+                output: cap(&output, MAX_OUTPUT_BYTES),
+            };
+            (test, failure)
+        })
+        .collect()
+}
+
+/// Truncate `s` to at most `max` bytes on a char boundary, noting how much
+/// was cut.
+fn cap(s: &str, max: usize) -> String {
+    if s.len() <= max {
+        return s.to_string();
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}… [{} more bytes]", &s[..end], s.len() - end)
 }
 
-/// Spawn-and-wait with a timeout, returning (stdout, stderr) on success.
-fn wait_with_timeout(
-    child: &mut std::process::Child,
-    timeout: Duration,
-) -> Option<(Vec<u8>, Vec<u8>)> {
+/// Convert a rustc JSON diagnostic, dropping the summary notes rustc appends
+/// ("aborting due to …", "N warnings emitted", "For more information …").
+fn parse_diagnostic(message: &serde_json::Value) -> Option<Diagnostic> {
+    let level = message["level"].as_str()?.to_string();
+    let text = message["message"].as_str()?.to_string();
+    if level == "failure-note"
+        || text.starts_with("aborting due to")
+        || (level == "warning" && text.ends_with("emitted"))
+    {
+        return None;
+    }
+    let span = message["spans"]
+        .as_array()
+        .and_then(|spans| spans.iter().find(|sp| sp["is_primary"] == true))
+        .and_then(|sp| {
+            Some(Span {
+                file: sp["file_name"].as_str()?.to_string(),
+                line: sp["line_start"].as_u64()? as usize,
+                column: sp["column_start"].as_u64()? as usize,
+            })
+        });
+    Some(Diagnostic {
+        level,
+        code: message["code"]["code"].as_str().map(str::to_string),
+        message: text,
+        span,
+    })
+}
+
+/// Delete one candidate's build outputs from the shared target directory.
+/// Every temp crate has a distinct path and therefore distinct artifact
+/// hashes, so they would otherwise accumulate across a sweep.
+fn remove_artifacts(artifacts: &[PathBuf]) {
+    for path in artifacts {
+        let _ = fs::remove_file(path);
+    }
+}
+
+struct ProcessOutput {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    success: bool,
+    timed_out: bool,
+    /// Signal that terminated the process, if it didn't exit normally.
+    signal: Option<i32>,
+}
+
+/// Wait for `child` for at most `timeout`, killing its process group once
+/// the limit is reached and, after a normal exit, anything the leader left
+/// running. On timeout, whatever it wrote before being killed is returned.
+fn wait_with_timeout(child: &mut std::process::Child, timeout: Duration) -> ProcessOutput {
     // Read pipes in separate threads to avoid deadlock when buffers fill.
-    let stdout_handle = child.stdout.take().map(|pipe| {
-        std::thread::spawn(move || {
-            let mut buf = Vec::new();
-            let _ = std::io::Read::read_to_end(&mut { pipe }, &mut buf);
-            buf
-        })
-    });
-    let stderr_handle = child.stderr.take().map(|pipe| {
-        std::thread::spawn(move || {
-            let mut buf = Vec::new();
-            let _ = std::io::Read::read_to_end(&mut { pipe }, &mut buf);
-            buf
-        })
-    });
+    let (stdout_buf, stdout_handle) = spawn_reader(child.stdout.take());
+    let (stderr_buf, stderr_handle) = spawn_reader(child.stderr.take());
 
-    let start = std::time::Instant::now();
-    loop {
-        match child.try_wait() {
-            Ok(Some(_status)) => break,
-            Ok(None) => {
+    let start = Instant::now();
+    // Kill the group before reaping its leader, whose pid is the group id.
+    let (status, timed_out) = loop {
+        match sandbox::has_exited(child) {
+            Ok(true) => {
+                sandbox::kill_tree(child);
+                break (child.wait().ok(), false);
+            }
+            Ok(false) => {
                 if start.elapsed() >= timeout {
-                    return None;
+                    sandbox::kill_tree(child);
+                    let _ = child.wait();
+                    break (None, true);
                 }
                 std::thread::sleep(Duration::from_millis(100));
             }
-            Err(_) => return None,
+            Err(_) => break (None, false),
+        }
+    };
+    #[cfg(unix)]
+    let signal = status.and_then(|s| std::os::unix::process::ExitStatusExt::signal(&s));
+    #[cfg(not(unix))]
+    let signal = None;
+
+    // A descendant that escaped the process group may still hold the pipes
+    // open, so only wait for the readers to drain them after a normal exit.
+    if !timed_out {
+        for handle in [stdout_handle, stderr_handle].into_iter().flatten() {
+            let _ = handle.join();
         }
     }
+    let take = |buf: Arc<Mutex<Vec<u8>>>| buf.lock().map(|b| b.clone()).unwrap_or_default();
+    ProcessOutput {
+        stdout: take(stdout_buf),
+        stderr: take(stderr_buf),
+        success: status.is_some_and(|s| s.success()),
+        timed_out,
+        signal,
+    }
+}
+
+/// Output kept per pipe; anything beyond is read and discarded, so a test
+/// printing in a loop can't exhaust memory.
+const MAX_CAPTURE_BYTES: usize = 4 << 20;
 
-    let stdout = stdout_handle.and_then(|h| h.join().ok()).unwrap_or_default();
-    let stderr = stderr_handle.and_then(|h| h.join().ok()).unwrap_or_default();
-    Some((stdout, stderr))
+/// Drain `pipe` on a background thread into a shared buffer of at most
+/// `MAX_CAPTURE_BYTES`.
+fn spawn_reader<R: Read + Send + 'static>(
+    pipe: Option<R>,
+) -> (Arc<Mutex<Vec<u8>>>, Option<std::thread::JoinHandle<()>>) {
+    let buf = Arc::new(Mutex::new(Vec::new()));
+    let handle = pipe.map(|mut pipe| {
+        let buf = Arc::clone(&buf);
+        std::thread::spawn(move || {
+            let mut chunk = [0u8; 8192];
+            while let Ok(n) = pipe.read(&mut chunk) {
+                if n == 0 {
+                    break;
+                }
+                if let Ok(mut b) = buf.lock() {
+                    let room = MAX_CAPTURE_BYTES.saturating_sub(b.len());
+                    b.extend_from_slice(&chunk[..n.min(room)]);
+                }
+            }
+        })
+    });
+    (buf, handle)
+}
+
+// ── Experiments ─────────────────────────────────────────────────────
+
+/// The subset of the runner's frozen `config.json` that eval needs.
+#[derive(Debug, Deserialize)]
+struct ExperimentConfig {
+    model: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    created_at: String,
+}
+
+struct Experiment {
+    id: String,
+    dir: PathBuf,
+    config: ExperimentConfig,
+}
+
+/// Resolve an experiment given as an ID under `results/` or as a path.
+fn open_experiment(results_dir: &Path, spec: &str) -> Result<Experiment, String> {
+    let dir = if Path::new(spec).join("config.json").is_file() {
+        PathBuf::from(spec)
+    } else {
+        results_dir.join(spec)
+    };
+    let config_path = dir.join("config.json");
+    let config_str = fs::read_to_string(&config_path)
+        .map_err(|e| format!("cannot read {}: {e}", config_path.display()))?;
+    let config: ExperimentConfig = serde_json::from_str(&config_str)
+        .map_err(|e| format!("cannot parse {}: {e}", config_path.display()))?;
+    let id = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(spec)
+        .to_string();
+    Ok(Experiment { id, dir, config })
+}
+
+/// Find the most recently created experiment under `results/`.
+fn latest_experiment(results_dir: &Path) -> Option<String> {
+    let entries = fs::read_dir(results_dir).ok()?;
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().join("config.json").is_file())
+        .map(|e| {
+            let created_at = fs::read_to_string(e.path().join("manifest.json"))
+                .ok()
+                .and_then(|s| serde_json::from_str::<Manifest>(&s).ok())
+                .map(|m| m.created_at)
+                .unwrap_or_default();
+            (created_at, e.file_name().to_string_lossy().to_string())
+        })
+        .max()
+        .map(|(_, id)| id)
 }
 
 // ── Main ────────────────────────────────────────────────────────────
 
+#[derive(Parser)]
+#[command(name = "eval", about = "Evaluate generated solutions against the task test suites")]
+struct Cli {
+    /// Experiment IDs under results/ (or experiment directories) to evaluate;
+    /// defaults to the most recently created experiment
+    experiments: Vec<String>,
+
+    /// Score responses even if the task's test cases changed after generation
+    #[arg(long)]
+    allow_drift: bool,
+
+    /// Evaluate only this cell, given as TASK_ID/STYLE/RUN (repeatable)
+    #[arg(long, value_name = "TASK_ID/STYLE/RUN")]
+    only: Vec<String>,
+
+    /// Write the combined results of all experiments here instead of to
+    /// each experiment's eval.json
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Wall-clock budget for building each response, separate from the
+    /// task's time limit, which applies to running its tests only
+    #[arg(long, value_name = "SECS", default_value_t = 120)]
+    compile_timeout: u64,
+
+    /// Cargo target directory shared by all candidate builds
+    /// (default: eval/target/candidates)
+    #[arg(long)]
+    target_dir: Option<PathBuf>,
+
+    /// Number of responses to build and test in parallel
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Address-space limit for a candidate's test binary, in MiB
+    #[arg(long, value_name = "MIB", default_value_t = 2048)]
+    memory_limit: u64,
+
+    /// Largest file a candidate's test binary may write, in MiB
+    #[arg(long, value_name = "MIB", default_value_t = 64)]
+    file_size_limit: u64,
+
+    /// Processes and threads a candidate's test binary may add to those the
+    /// user already runs
+    #[arg(long, value_name = "N", default_value_t = 256)]
+    max_processes: u64,
+
+    /// Wall-clock budget for a task's benchmark, shared by the candidate and
+    /// the reference
+    #[arg(long, value_name = "SECS", default_value_t = 60)]
+    bench_timeout: u64,
+
+    /// Run each compiled test binary K more times, under varied test threads
+    /// and CPU contention, and mark tests that pass only sometimes as flaky
+    #[arg(long, value_name = "K", default_value_t = 0)]
+    reruns: usize,
+}
+
+/// Settings that apply to every evaluated response.
+struct EvalOptions {
+    allow_drift: bool,
+    /// Cells to evaluate as `task_id/style/run`; empty means all.
+    only: HashSet<String>,
+    compile_timeout: Duration,
+    bench_timeout: Duration,
+    /// Extra runs of each test binary to detect flaky tests.
+    reruns: usize,
+    /// Root of the per-worker target directories.
+    target_dir: PathBuf,
+    jobs: usize,
+    /// CPUs each job may use, for cargo's `--jobs` and `RUST_TEST_THREADS`,
+    /// so that concurrent jobs don't starve each other's timed tests.
+    cpus_per_job: usize,
+    /// CPUs this process may run on; with several jobs, each worker is
+    /// pinned to its own `cpus_per_job` of them.
+    allowed_cpus: Vec<usize>,
+    /// Limits for candidate test binaries; the CPU limit is set per task.
+    limits: sandbox::Limits,
+    /// `cargo clippy` is installed, so compiled candidates get linted.
+    clippy: bool,
+    /// `rustfmt` is installed, so formatting gets checked.
+    rustfmt: bool,
+}
+
 fn main() {
+    let cli = Cli::parse();
     let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
     let results_dir = project_root.join("results");
     let tasks_dir = project_root.join("tasks").join("definitions");
-    let output_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("results.json");
 
-    if !results_dir.is_dir() {
-        eprintln!("results/ directory not found at {}", results_dir.display());
-        std::process::exit(1);
+    let specs = if cli.experiments.is_empty() {
+        match latest_experiment(&results_dir) {
+            Some(id) => {
+                println!("No experiment given; evaluating latest: {id}");
+                vec![id]
+            }
+            None => {
+                eprintln!("No experiments found under {}", results_dir.display());
+                std::process::exit(1);
+            }
+        }
+    } else {
+        cli.experiments.clone()
+    };
+
+    let experiments: Vec<Experiment> = specs
+        .iter()
+        .map(|spec| {
+            open_experiment(&results_dir, spec).unwrap_or_else(|e| {
+                eprintln!("Experiment '{spec}': {e}");
+                std::process::exit(1);
+            })
+        })
+        .collect();
+
+    let jobs = cli.jobs.max(1);
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if jobs > cpus {
+        eprintln!(
+            "⚠ --jobs {jobs} exceeds the {cpus} available CPU(s); test timings will be noisy"
+        );
+    }
+
+    let opts = EvalOptions {
+        allow_drift: cli.allow_drift,
+        only: cli.only.iter().cloned().collect(),
+        compile_timeout: Duration::from_secs(cli.compile_timeout),
+        bench_timeout: Duration::from_secs(cli.bench_timeout),
+        reruns: cli.reruns,
+        target_dir: cli
+            .target_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/candidates")),
+        jobs,
+        cpus_per_job: (cpus / jobs).max(1),
+        allowed_cpus: sandbox::allowed_cpus(),
+        limits: sandbox::Limits {
+            cpu: Duration::ZERO,
+            memory_bytes: cli.memory_limit << 20,
+            file_size_bytes: cli.file_size_limit << 20,
+            max_processes: sandbox::user_task_count() + cli.max_processes,
+        },
+        clippy: clippy::available(),
+        rustfmt: rustfmt::available(),
+    };
+    if !opts.clippy {
+        eprintln!("⚠ cargo clippy not found; lint counts will be missing");
+    }
+    if !opts.rustfmt {
+        eprintln!("⚠ rustfmt not found; formatting will not be checked");
+    }
+    if sandbox::network_isolation().is_none() {
+        eprintln!("⚠ network namespaces unavailable; candidate tests run with network access");
+    }
+
+    // Cache loaded tasks
+    let mut task_cache: HashMap<String, TaskFile> = HashMap::new();
+    let mut combined: Vec<EvalResult> = Vec::new();
+
+    for experiment in &experiments {
+        println!("═══ experiment {} ({}) ═══", experiment.id, experiment.config.model);
+        let eval_results = evaluate_experiment(experiment, &tasks_dir, &mut task_cache, &opts);
+
+        if cli.output.is_some() {
+            combined.extend(eval_results);
+            continue;
+        }
+        let output_path = experiment.dir.join("eval.json");
+        let json = serde_json::to_string_pretty(&eval_results).expect("serialize results");
+        fs::write(&output_path, &json).expect("write eval.json");
+        println!("\n✔ Wrote {} results to {}", eval_results.len(), output_path.display());
     }
 
-    // Collect .rs files
-    let rs_files: Vec<PathBuf> = WalkDir::new(&results_dir)
+    if let Some(output_path) = &cli.output {
+        let json = serde_json::to_string_pretty(&combined).expect("serialize results");
+        fs::write(output_path, &json).expect("write results");
+        println!("\n✔ Wrote {} results to {}", combined.len(), output_path.display());
+    }
+}
+
+/// A response queued for building and testing.
+struct Job {
+    task_id: String,
+    style: String,
+    run: u32,
+    /// The response as saved, and the code extracted from it.
+    raw_code: String,
+    extraction: extract::Extraction,
+    meta: Option<Meta>,
+    task_drift: bool,
+    tests_drift: bool,
+    log: JobLog,
+}
+
+fn evaluate_experiment(
+    experiment: &Experiment,
+    tasks_dir: &Path,
+    task_cache: &mut HashMap<String, TaskFile>,
+    opts: &EvalOptions,
+) -> Vec<EvalResult> {
+    // Collect .rs files: {experiment}/{task_id}/{style}/run_{N}.rs
+    let mut rs_files: Vec<PathBuf> = WalkDir::new(&experiment.dir)
+        .min_depth(3)
+        .max_depth(3)
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| {
-            e.path().extension().map_or(false, |ext| ext == "rs")
+            e.path().extension().is_some_and(|ext| ext == "rs")
         })
         .map(|e| e.into_path())
         .collect();
+    rs_files.sort();
 
     if rs_files.is_empty() {
         eprintln!("No .rs result files found; writing empty results.");
-        let empty: Vec<EvalResult> = Vec::new();
-        fs::write(&output_path, serde_json::to_string_pretty(&empty).unwrap())
-            .expect("failed to write results.json");
-        return;
     }
 
-    // Cache loaded tasks
-    let mut task_cache: HashMap<String, TaskFile> = HashMap::new();
-    let mut eval_results: Vec<EvalResult> = Vec::new();
+    let mut jobs: Vec<Job> = Vec::new();
+    let mut drift_skipped = 0usize;
 
     for rs_path in &rs_files {
-        // Parse path: results/{task_id}/{style}/{run_n}.rs
+        // Parse path: {task_id}/{style}/run_{N}.rs
         let rel = rs_path
-            .strip_prefix(&results_dir)
-            .expect("should be under results/");
+            .strip_prefix(&experiment.dir)
+            .expect("should be under the experiment directory");
         let components: Vec<&str> = rel
             .components()
             .filter_map(|c| {
@@ -374,38 +1546,47 @@ fn main() {
             })
             .collect();
 
-        if components.len() != 3 {
-            eprintln!(
-                "Skipping {}: expected results/{{task_id}}/{{style}}/{{run}}.rs",
-                rs_path.display()
-            );
-            continue;
-        }
-
         let task_id = components[0].to_string();
         let style = components[1].to_string();
-        let run_file = components[2]; // e.g. "1.rs"
+        let run_file = components[2]; // e.g. "run_1.rs"
         let run: u32 = run_file
             .trim_end_matches(".rs")
+            .trim_start_matches("run_")
             .parse()
             .unwrap_or_else(|_| {
                 eprintln!("Cannot parse run number from {run_file}, defaulting to 0");
                 0
             });
 
-        println!("▶ {task_id} / {style} / run {run}");
+        if !opts.only.is_empty() && !opts.only.contains(&format!("{task_id}/{style}/{run}")) {
+            continue;
+        }
+
+        let mut log = JobLog::new(format!("▶ {task_id} / {style} / run {run}"));
 
         // Load task definition
         if !task_cache.contains_key(&task_id) {
             let toml_path = tasks_dir.join(format!("{task_id}.toml"));
             if !toml_path.is_file() {
-                eprintln!("  ✗ task file not found: {}", toml_path.display());
+                log.err(format!("  ✗ task file not found: {}", toml_path.display()));
+                log.flush();
                 continue;
             }
             let toml_str = fs::read_to_string(&toml_path).expect("read task toml");
-            let parsed: TaskFile = toml::from_str(&toml_str).unwrap_or_else(|e| {
+            let mut parsed: TaskFile = toml::from_str(&toml_str).unwrap_or_else(|e| {
                 panic!("Failed to parse {}: {e}", toml_path.display());
             });
+            parsed.definition_hash = sha256_hex(toml_str.as_bytes());
+            if parsed.task.properties.is_some() || parsed.task.benchmark.is_some() {
+                let path = tasks_dir.with_file_name("solutions").join(&task_id).join("src/lib.rs");
+                match fs::read_to_string(&path) {
+                    Ok(code) => parsed.reference = Some(code),
+                    Err(e) => log.err(format!(
+                        "  ⚠ reference solution missing: cannot read {}: {e}",
+                        path.display()
+                    )),
+                }
+            }
             task_cache.insert(task_id.clone(), parsed);
         }
         let task_def = &task_cache[&task_id];
@@ -414,11 +1595,12 @@ fn main() {
         let raw_code = match fs::read_to_string(rs_path) {
             Ok(c) => c,
             Err(e) => {
-                eprintln!("  ✗ cannot read {}: {e}", rs_path.display());
+                log.err(format!("  ✗ cannot read {}: {e}", rs_path.display()));
+                log.flush();
                 continue;
             }
         };
-        let (code, had_markdown_fences) = strip_markdown_fences(&raw_code);
+        let extraction = extract::extract(&raw_code);
 
         // Read .meta.json
         let meta_path = rs_path.with_extension("meta.json");
@@ -431,72 +1613,264 @@ fn main() {
             None
         };
 
-        // Static metrics
-        let loc = count_loc(&code);
-        let unsafe_count = count_unsafe(&code);
-        let unwrap_count = count_pattern(&code, ".unwrap()");
-        let expect_count = count_pattern(&code, ".expect(");
-
-        let must_use_satisfied = task_def
-            .task
-            .constraints
-            .must_use
-            .iter()
-            .all(|item| code.contains(item.as_str()));
-
-        let forbidden_violated = task_def
-            .task
-            .constraints
-            .forbidden
-            .iter()
-            .any(|item| code.contains(item.as_str()));
-
-        // Run tests
-        let timeout = Duration::from_secs(task_def.task.constraints.time_limit_seconds);
-        let (compiled, test_results) =
-            run_tests(&code, &task_def.task.tests.cases, timeout);
-
-        let tests_total = task_def.task.tests.cases.len();
-        let tests_failed: Vec<String> = task_def
-            .task
-            .tests
-            .cases
-            .iter()
-            .filter(|tc| !test_results.get(&tc.name).copied().unwrap_or(false))
-            .map(|tc| tc.name.clone())
-            .collect();
-        let tests_passed = tests_total - tests_failed.len();
-
-        let status = if compiled {
-            format!("{tests_passed}/{tests_total} tests passed")
-        } else {
-            "did not compile".to_string()
-        };
-        println!("  → {status}");
+        // Provenance: was this response generated against the current task?
+        let task_drift = meta
+            .as_ref()
+            .and_then(|m| m.task_hash.as_deref())
+            .is_some_and(|h| h != task_def.definition_hash);
+        let tests_drift = meta
+            .as_ref()
+            .and_then(|m| m.tests_hash.as_deref())
+            .is_some_and(|h| h != hash_test_cases(&task_def.task.tests.cases));
+        if tests_drift && !opts.allow_drift {
+            log.err(
+                "  ✗ test cases changed since this response was generated; \
+                 skipping (pass --allow-drift to score it anyway)"
+                    .to_string(),
+            );
+            log.flush();
+            drift_skipped += 1;
+            continue;
+        }
+        if tests_drift {
+            log.err("  ⚠ test cases changed since this response was generated".to_string());
+        } else if task_drift {
+            log.err("  ⚠ task definition changed since this response was generated".to_string());
+        }
 
-        eval_results.push(EvalResult {
+        jobs.push(Job {
             task_id,
             style,
             run,
-            compiled,
-            tests_total,
-            tests_passed,
-            tests_failed,
-            loc,
-            unsafe_count,
-            unwrap_count,
-            expect_count,
-            must_use_satisfied,
-            forbidden_violated,
-            had_markdown_fences,
-            latency_ms: meta.as_ref().map_or(0, |m| m.latency_ms),
-            input_tokens: meta.as_ref().map_or(0, |m| m.input_tokens),
-            output_tokens: meta.as_ref().map_or(0, |m| m.output_tokens),
+            raw_code,
+            extraction,
+            meta,
+            task_drift,
+            tests_drift,
+            log,
         });
     }
 
-    // Write output
-    let json = serde_json::to_string_pretty(&eval_results).expect("serialize results");
-    fs::write(&output_path, &json).expect("write results.json");
-    println!("\n✔ Wrote {} results to {}", eval_results.len(), output_path.display());
+    if drift_skipped > 0 {
+        eprintln!(
+            "⚠ {drift_skipped} response(s) skipped because their test cases changed \
+             since generation"
+        );
+    }
+
+    // Build and test on `opts.jobs` workers, each with its own target dir.
+    // Results land in their job's slot, so the output order is the sorted
+    // path order regardless of scheduling.
+    let task_cache = &*task_cache;
+    let slots: Vec<Mutex<Option<EvalResult>>> = jobs.iter().map(|_| Mutex::new(None)).collect();
+    let queue = Mutex::new(jobs.into_iter().enumerate());
+    std::thread::scope(|scope| {
+        for worker in 0..opts.jobs.min(slots.len()) {
+            let target_dir = opts.target_dir.join(format!("worker-{worker}"));
+            let (queue, slots) = (&queue, &slots);
+            scope.spawn(move || {
+                if opts.jobs > 1 && !opts.allowed_cpus.is_empty() {
+                    let cpus = cpu_slice(&opts.allowed_cpus, worker, opts.cpus_per_job);
+                    if let Err(e) = sandbox::pin_thread(&cpus) {
+                        eprintln!("⚠ could not pin worker {worker} to CPUs {cpus:?}: {e}");
+                    }
+                }
+                loop {
+                    let Some((i, job)) = queue.lock().expect("job queue").next() else {
+                        break;
+                    };
+                    let task_def = &task_cache[&job.task_id];
+                    let result = evaluate_job(experiment, task_def, job, opts, &target_dir);
+                    *slots[i].lock().expect("result slot") = Some(result);
+                }
+            });
+        }
+    });
+
+    slots
+        .into_iter()
+        .filter_map(|slot| slot.into_inner().expect("result slot"))
+        .collect()
+}
+
+/// The `per_job` CPUs of `worker`, taken in turn from `cpus`; workers share
+/// CPUs only when there are more jobs than CPUs.
+fn cpu_slice(cpus: &[usize], worker: usize, per_job: usize) -> Vec<usize> {
+    (0..per_job)
+        .map(|i| cpus[(worker * per_job + i) % cpus.len()])
+        .collect()
+}
+
+/// Compute the static metrics for one response and run its tests.
+fn evaluate_job(
+    experiment: &Experiment,
+    task_def: &TaskFile,
+    job: Job,
+    opts: &EvalOptions,
+    target_dir: &Path,
+) -> EvalResult {
+    let Job {
+        task_id,
+        style,
+        run,
+        raw_code,
+        extraction,
+        meta,
+        task_drift,
+        tests_drift,
+        mut log,
+    } = job;
+    let response_kind = response_kind(&raw_code, &extraction);
+    let code = extraction.code;
+
+    // Static metrics
+    let metrics = metrics::analyze(&code);
+    let placeholders = placeholders::detect(&code);
+    let formatting = if opts.rustfmt {
+        rustfmt::check(&code)
+    } else {
+        rustfmt::Formatting::default()
+    };
+
+    let rules = &task_def.task.constraints;
+    let check = constraints::check(&code, &rules.must_use, &rules.forbidden);
+    for e in &check.forbidden {
+        log.err(format!(
+            "  ⚠ uses forbidden {} ({}) at {}:{}",
+            e.item, e.path, e.line, e.column
+        ));
+    }
+    let lib_code = if rules.forbidden.iter().any(|i| i == constraints::UNSAFE) {
+        format!("{}{code}", constraints::FORBID_UNSAFE)
+    } else {
+        code.clone()
+    };
+
+    // Run tests
+    let mut test_run = run_tests(&lib_code, task_def, opts, target_dir, &mut log);
+    let compiled = test_run.compiled;
+
+    let tests_total = task_def.task.tests.cases.len();
+    let tests_failed: Vec<String> = task_def
+        .task
+        .tests
+        .cases
+        .iter()
+        .filter(|tc| !test_run.results.get(&tc.name).copied().unwrap_or(false))
+        .map(|tc| tc.name.clone())
+        .collect();
+    let tests_passed = tests_total - tests_failed.len();
+
+    let count_set = |set: TestSet| {
+        let cases = task_def.task.tests.cases.iter().filter(|tc| tc.set == set);
+        let passed = cases.clone().filter(|tc| !tests_failed.contains(&tc.name)).count();
+        (cases.count(), passed)
+    };
+    let (visible_total, visible_passed) = count_set(TestSet::Visible);
+    let (hidden_total, hidden_passed) = count_set(TestSet::Hidden);
+    let visibility_gap = (visible_total > 0 && hidden_total > 0).then(|| {
+        visible_passed as f64 / visible_total as f64 - hidden_passed as f64 / hidden_total as f64
+    });
+    if compiled && visible_passed == visible_total && hidden_passed < hidden_total {
+        if let Some(gap) = visibility_gap {
+            log.err(format!(
+                "  ⚠ passes every visible test but {hidden_passed}/{hidden_total} hidden \
+                 (gap {gap:.2})"
+            ));
+        }
+    }
+
+    let errors = test_run.diagnostics.iter().filter(|d| d.level == "error");
+    let error_count = errors.clone().count();
+    let error_codes: Vec<String> = errors.filter_map(|d| d.code.clone()).collect();
+    let warning_count = test_run
+        .diagnostics
+        .iter()
+        .filter(|d| d.level == "warning")
+        .count();
+
+    let finish_reason = meta.as_ref().and_then(|m| m.finish_reason.as_deref());
+    let mut failure_by_test = std::mem::take(&mut test_run.failures);
+    let failures: Vec<TestFailure> = tests_failed
+        .iter()
+        .filter_map(|name| failure_by_test.remove(name))
+        .collect();
+    for failure in &failures {
+        let detail = failure.message.as_deref().unwrap_or("no panic message");
+        let first_line = detail.lines().next().unwrap_or_default();
+        log.out(format!("    {}: {first_line}", failure.test));
+    }
+
+    let all_passed = tests_passed == tests_total;
+    let outcome = classify(&test_run, &raw_code, &code, finish_reason, all_passed);
+
+    let status = if test_run.test_timed_out {
+        format!("{tests_passed}/{tests_total} tests passed before the time limit")
+    } else if compiled && test_run.isolated {
+        format!(
+            "{tests_passed}/{tests_total} tests passed ({} failed to compile)",
+            test_run.tests_compile_failed.len()
+        )
+    } else if compiled {
+        format!("{tests_passed}/{tests_total} tests passed")
+    } else if test_run.compile_timed_out {
+        "compile timed out".to_string()
+    } else {
+        "did not compile".to_string()
+    };
+    log.out(format!("  → {status} [{}]", outcome.as_str()));
+    log.flush();
+
+    EvalResult {
+        experiment_id: experiment.id.clone(),
+        model: experiment.config.model.clone(),
+        task_id,
+        style,
+        run,
+        compiled,
+        tests_total,
+        tests_passed,
+        tests_failed,
+        failures,
+        visible_total,
+        visible_passed,
+        hidden_total,
+        hidden_passed,
+        visibility_gap,
+        outcome,
+        infra_error: test_run.infra_error,
+        crash_signal: test_run.crash_signal,
+        tests_compile_failed: test_run.tests_compile_failed,
+        isolated: test_run.isolated,
+        error_codes,
+        error_count,
+        warning_count,
+        diagnostics: test_run.diagnostics,
+        compile_timed_out: test_run.compile_timed_out,
+        test_timed_out: test_run.test_timed_out,
+        metrics,
+        placeholders,
+        lints: test_run.lints,
+        own_tests: test_run.own_tests,
+        differential: test_run.differential,
+        benchmark: test_run.benchmark,
+        reruns: test_run.reruns,
+        formatting,
+        must_use_satisfied: check.must_use_satisfied,
+        must_use_evidence: check.must_use,
+        forbidden_violated: check.forbidden_violated,
+        forbidden_evidence: check.forbidden,
+        extraction: extraction.strategy,
+        had_markdown_fences: extraction.had_markdown_fences,
+        response_kind,
+        prose_lines: extraction.prose_lines,
+        prose_chars: extraction.prose_chars,
+        task_drift,
+        tests_drift,
+        compile_ms: test_run.compile_ms,
+        test_ms: test_run.test_ms,
+        latency_ms: meta.as_ref().map_or(0, |m| m.latency_ms),
+        input_tokens: meta.as_ref().map_or(0, |m| m.input_tokens),
+        output_tokens: meta.as_ref().map_or(0, |m| m.output_tokens),
+    }
 }
@@ -0,0 +1,344 @@
+//! Static metrics computed from the syntax tree rather than the raw text,
+//! so comments and string literals don't count and `unsafe fn`/`unsafe impl`
+//! are seen. Code under `#[cfg(test)]` or `#[test]` is skipped: the model's
+//! own tests are allowed to unwrap.
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use syn::{Attribute, BinOp, Expr, Token};
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Metrics {
+    /// Whether the code parsed; if not, only `loc` is filled in (from text).
+    pub ast_parsed: bool,
+    /// Lines holding at least one token, excluding comments and doc comments.
+    pub loc: usize,
+    /// Sum of the three `unsafe_*` counts.
+    pub unsafe_count: usize,
+    pub unsafe_blocks: usize,
+    pub unsafe_fns: usize,
+    pub unsafe_impls: usize,
+    pub unwrap_count: usize,
+    pub expect_count: usize,
+    /// `panic!`, `unreachable!`, `todo!` and `unimplemented!`.
+    pub panic_count: usize,
+    /// Index expressions (`a[i]`), which panic when out of bounds.
+    pub index_count: usize,
+    pub clone_count: usize,
+    /// Functions and methods with a body.
+    pub fn_count: usize,
+    /// Function length in lines, signature included.
+    pub fn_len_max: usize,
+    pub fn_len_mean: f64,
+    /// McCabe complexity per function: 1 + branches (`if`, loops, extra
+    /// match arms, `&&`, `||`).
+    pub cyclomatic_max: usize,
+    pub cyclomatic_mean: f64,
+    /// Deepest nesting of control flow and closures inside any function.
+    pub max_nesting: usize,
+}
+
+pub fn analyze(code: &str) -> Metrics {
+    let Ok(file) = syn::parse_file(code) else {
+        return Metrics {
+            loc: count_loc_text(code),
+            ..Metrics::default()
+        };
+    };
+    let mut visitor = MetricsVisitor::default();
+    visitor.visit_file(&file);
+
+    let mut m = visitor.metrics;
+    m.ast_parsed = true;
+    m.loc = code
+        .parse::<TokenStream>()
+        .map(|tokens| {
+            let mut lines = BTreeSet::new();
+            token_lines(tokens, &mut lines);
+            lines.len()
+        })
+        .unwrap_or_else(|_| count_loc_text(code));
+    m.unsafe_count = m.unsafe_blocks + m.unsafe_fns + m.unsafe_impls;
+    m.fn_count = visitor.fns.len();
+    if !visitor.fns.is_empty() {
+        let n = visitor.fns.len() as f64;
+        m.fn_len_max = visitor.fns.iter().map(|f| f.lines).max().unwrap_or(0);
+        m.fn_len_mean = visitor.fns.iter().map(|f| f.lines).sum::<usize>() as f64 / n;
+        m.cyclomatic_max = visitor.fns.iter().map(|f| f.complexity).max().unwrap_or(0);
+        m.cyclomatic_mean = visitor.fns.iter().map(|f| f.complexity).sum::<usize>() as f64 / n;
+        m.max_nesting = visitor.fns.iter().map(|f| f.max_depth).max().unwrap_or(0);
+    }
+    m
+}
+
+/// Fallback line count for code that doesn't parse: non-blank lines that
+/// aren't `//` comments.
+fn count_loc_text(code: &str) -> usize {
+    code.lines()
+        .filter(|l| {
+            let t = l.trim();
+            !t.is_empty() && !t.starts_with("//")
+        })
+        .count()
+}
+
+/// Collect the lines covered by tokens, skipping `#[doc = …]` attributes,
+/// which is what doc comments become.
+fn token_lines(tokens: TokenStream, lines: &mut BTreeSet<usize>) {
+    let trees: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut i = 0;
+    while i < trees.len() {
+        if let Some(skip) = doc_attribute_len(&trees[i..]) {
+            i += skip;
+            continue;
+        }
+        match &trees[i] {
+            TokenTree::Group(g) => {
+                lines.insert(g.span_open().start().line);
+                lines.insert(g.span_close().start().line);
+                token_lines(g.stream(), lines);
+            }
+            tt => {
+                let span = tt.span();
+                lines.extend(span.start().line..=span.end().line);
+            }
+        }
+        i += 1;
+    }
+}
+
+/// Number of token trees making up a doc attribute at the start of `trees`.
+fn doc_attribute_len(trees: &[TokenTree]) -> Option<usize> {
+    let TokenTree::Punct(hash) = trees.first()? else {
+        return None;
+    };
+    if hash.as_char() != '#' {
+        return None;
+    }
+    let bang = matches!(trees.get(1), Some(TokenTree::Punct(p)) if p.as_char() == '!');
+    let group_at = if bang { 2 } else { 1 };
+    let TokenTree::Group(group) = trees.get(group_at)? else {
+        return None;
+    };
+    let is_doc = group.delimiter() == Delimiter::Bracket
+        && matches!(group.stream().into_iter().next(), Some(TokenTree::Ident(id)) if id == "doc");
+    is_doc.then_some(group_at + 1)
+}
+
+struct FnStats {
+    lines: usize,
+    complexity: usize,
+    depth: usize,
+    max_depth: usize,
+}
+
+#[derive(Default)]
+struct MetricsVisitor {
+    metrics: Metrics,
+    fns: Vec<FnStats>,
+    /// Indices into `fns` of the functions being visited, innermost last.
+    stack: Vec<usize>,
+}
+
+/// `#[test]` functions and `#[cfg(test)]` items.
+pub fn is_test_code(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        let path = attr.path();
+        if path.is_ident("test") {
+            return true;
+        }
+        if path.is_ident("cfg") {
+            let mut test = false;
+            let _ = attr.parse_nested_meta(|meta| {
+                test |= meta.path.is_ident("test");
+                Ok(())
+            });
+            return test;
+        }
+        false
+    })
+}
+
+impl MetricsVisitor {
+    fn current(&mut self) -> Option<&mut FnStats> {
+        let i = *self.stack.last()?;
+        self.fns.get_mut(i)
+    }
+
+    fn branch(&mut self, n: usize) {
+        if let Some(f) = self.current() {
+            f.complexity += n;
+        }
+    }
+
+    /// Visit `inner` one nesting level deeper.
+    fn nested(&mut self, inner: impl FnOnce(&mut Self)) {
+        if let Some(f) = self.current() {
+            f.depth += 1;
+            f.max_depth = f.max_depth.max(f.depth);
+        }
+        inner(self);
+        if let Some(f) = self.current() {
+            f.depth -= 1;
+        }
+    }
+
+    /// Count a function from its `fn` keyword to its closing brace, so
+    /// attributes and doc comments don't add to its length.
+    fn function(&mut self, sig: &syn::Signature, block: &syn::Block, body: impl FnOnce(&mut Self)) {
+        let (start, end) = (
+            sig.fn_token.span.start(),
+            block.brace_token.span.close().end(),
+        );
+        self.fns.push(FnStats {
+            lines: end.line + 1 - start.line,
+            complexity: 1,
+            depth: 0,
+            max_depth: 0,
+        });
+        self.stack.push(self.fns.len() - 1);
+        body(self);
+        self.stack.pop();
+    }
+}
+
+impl<'ast> Visit<'ast> for MetricsVisitor {
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        if !is_test_code(&node.attrs) {
+            visit::visit_item_mod(self, node);
+        }
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        if is_test_code(&node.attrs) {
+            return;
+        }
+        if node.sig.unsafety.is_some() {
+            self.metrics.unsafe_fns += 1;
+        }
+        self.function(&node.sig, &node.block, |v| visit::visit_item_fn(v, node));
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        if is_test_code(&node.attrs) {
+            return;
+        }
+        if node.sig.unsafety.is_some() {
+            self.metrics.unsafe_fns += 1;
+        }
+        self.function(&node.sig, &node.block, |v| {
+            visit::visit_impl_item_fn(v, node)
+        });
+    }
+
+    fn visit_trait_item_fn(&mut self, node: &'ast syn::TraitItemFn) {
+        if node.sig.unsafety.is_some() {
+            self.metrics.unsafe_fns += 1;
+        }
+        if let Some(block) = &node.default {
+            self.function(&node.sig, block, |v| visit::visit_trait_item_fn(v, node));
+        } else {
+            visit::visit_trait_item_fn(self, node);
+        }
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        if is_test_code(&node.attrs) {
+            return;
+        }
+        if node.unsafety.is_some() {
+            self.metrics.unsafe_impls += 1;
+        }
+        visit::visit_item_impl(self, node);
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &'ast syn::ExprUnsafe) {
+        self.metrics.unsafe_blocks += 1;
+        visit::visit_expr_unsafe(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        match node.method.to_string().as_str() {
+            "unwrap" if node.args.is_empty() => self.metrics.unwrap_count += 1,
+            "expect" if node.args.len() == 1 => self.metrics.expect_count += 1,
+            "clone" if node.args.is_empty() => self.metrics.clone_count += 1,
+            _ => {}
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_expr_index(&mut self, node: &'ast syn::ExprIndex) {
+        self.metrics.index_count += 1;
+        visit::visit_expr_index(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        if let Some(name) = node.path.segments.last().map(|s| s.ident.to_string()) {
+            if matches!(
+                name.as_str(),
+                "panic" | "unreachable" | "todo" | "unimplemented"
+            ) {
+                self.metrics.panic_count += 1;
+            }
+        }
+        // Arguments of expression-like macros (`println!`, `vec!`, `assert!`,
+        // …) are ordinary expressions; look inside them too.
+        if let Ok(args) = node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        visit::visit_macro(self, node);
+    }
+
+    fn visit_expr_if(&mut self, node: &'ast syn::ExprIf) {
+        self.branch(1);
+        for attr in &node.attrs {
+            self.visit_attribute(attr);
+        }
+        self.nested(|v| {
+            v.visit_expr(&node.cond);
+            v.visit_block(&node.then_branch);
+        });
+        if let Some((_, else_branch)) = &node.else_branch {
+            match &**else_branch {
+                // `else if` continues the chain at the same depth.
+                Expr::If(_) => self.visit_expr(else_branch),
+                other => self.nested(|v| v.visit_expr(other)),
+            }
+        }
+    }
+
+    fn visit_expr_match(&mut self, node: &'ast syn::ExprMatch) {
+        self.branch(node.arms.len().saturating_sub(1));
+        self.nested(|v| visit::visit_expr_match(v, node));
+    }
+
+    fn visit_expr_while(&mut self, node: &'ast syn::ExprWhile) {
+        self.branch(1);
+        self.nested(|v| visit::visit_expr_while(v, node));
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        self.branch(1);
+        self.nested(|v| visit::visit_expr_for_loop(v, node));
+    }
+
+    fn visit_expr_loop(&mut self, node: &'ast syn::ExprLoop) {
+        self.nested(|v| visit::visit_expr_loop(v, node));
+    }
+
+    fn visit_expr_closure(&mut self, node: &'ast syn::ExprClosure) {
+        self.nested(|v| visit::visit_expr_closure(v, node));
+    }
+
+    fn visit_expr_binary(&mut self, node: &'ast syn::ExprBinary) {
+        if matches!(node.op, BinOp::And(_) | BinOp::Or(_)) {
+            self.branch(1);
+        }
+        visit::visit_expr_binary(self, node);
+    }
+}
@@ -0,0 +1,222 @@
+//! The candidate's own tests: `#[test]` functions it wrote and examples in
+//! its doc comments. They are counted from the syntax tree, then built and
+//! run apart from the task's tests, so their results never mix with the
+//! score.
+
+use crate::metrics::is_test_code;
+use crate::{sandbox, wait_with_timeout, EvalOptions, ProcessOutput};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use syn::visit::{self, Visit};
+use tempfile::TempDir;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OwnTests {
+    /// The response has unit tests or doc examples.
+    pub wrote_tests: bool,
+    pub own_unit_tests: usize,
+    /// The library's test harness compiled.
+    pub own_unit_compiled: bool,
+    pub own_unit_passed: usize,
+    pub own_doctests: usize,
+    pub own_doctests_passed: usize,
+}
+
+/// Count the candidate's `#[test]` functions and doctests.
+pub fn count(code: &str) -> OwnTests {
+    let Ok(file) = syn::parse_file(code) else {
+        return OwnTests::default();
+    };
+    let mut counter = Counter::default();
+    counter.visit_file(&file);
+    let own_doctests = count_doctests(&counter.docs);
+    OwnTests {
+        wrote_tests: counter.tests > 0 || own_doctests > 0,
+        own_unit_tests: counter.tests,
+        own_doctests,
+        ..OwnTests::default()
+    }
+}
+
+/// Build and run the unit tests and doctests counted in `tests`, in the temp
+/// crate at `crate_dir` whose library has already been built. Returns the
+/// files written to the shared target dir.
+pub fn run(
+    tests: &mut OwnTests,
+    crate_dir: &Path,
+    time_limit: Duration,
+    opts: &EvalOptions,
+    target_dir: &Path,
+) -> std::io::Result<Vec<PathBuf>> {
+    let mut artifacts = Vec::new();
+    if tests.own_unit_tests > 0 {
+        let mut cmd = Command::new("cargo");
+        sandbox::own_process_group(&mut cmd)
+            .args([
+                "test",
+                "--release",
+                "--offline",
+                "--lib",
+                "--no-run",
+                "--message-format=json",
+            ])
+            .arg(format!("--jobs={}", opts.cpus_per_job));
+        let mut child = cmd
+            .env("CARGO_TARGET_DIR", target_dir)
+            .current_dir(crate_dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let build = wait_with_timeout(&mut child, opts.compile_timeout);
+        let mut exe = None;
+        for line in String::from_utf8_lossy(&build.stdout).lines() {
+            let Ok(msg) = serde_json::from_str::<serde_json::Value>(line) else {
+                continue;
+            };
+            if msg["reason"] != "compiler-artifact" {
+                continue;
+            }
+            if let Some(files) = msg["filenames"].as_array() {
+                artifacts.extend(files.iter().filter_map(|f| f.as_str()).map(PathBuf::from));
+            }
+            if let Some(path) = msg["executable"].as_str() {
+                exe = Some(PathBuf::from(path));
+            }
+        }
+        if let Some(exe) = exe.filter(|_| build.success) {
+            tests.own_unit_compiled = true;
+            let output = crate::run_test_binary(&exe, time_limit, opts)?;
+            tests.own_unit_passed = passed(&output);
+        }
+    }
+
+    if tests.own_doctests > 0 {
+        // rustdoc compiles and runs each example itself, so the whole
+        // `cargo test --doc` is confined. Its HOME is the scratch dir, so
+        // rustup and cargo have to be told where they live. Compiling may
+        // need more address space than a test binary gets, so that limit is
+        // left to a runtool that sets it on each example it runs.
+        let scratch = TempDir::new()?;
+        let home = std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+        let rustup_home = std::env::var_os("RUSTUP_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".rustup"));
+        let cargo_home = std::env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".cargo"));
+        let timeout = opts.compile_timeout + time_limit;
+        let limits = sandbox::Limits {
+            cpu: timeout,
+            memory_bytes: u64::MAX,
+            ..opts.limits
+        };
+        let runtool = format!(
+            "ulimit -v {} && exec \"$0\" \"$@\"",
+            opts.limits.memory_bytes >> 10
+        );
+        let rustdoc_flags = [
+            "--test-runtool",
+            "sh",
+            "--test-runtool-arg",
+            "-c",
+            "--test-runtool-arg",
+            &runtool,
+        ]
+        .join("\x1f");
+        let mut cmd = Command::new("cargo");
+        let mut child = sandbox::confine(&mut cmd, limits, scratch.path())
+            .args(["test", "--release", "--offline", "--doc", "--manifest-path"])
+            .arg(crate_dir.join("Cargo.toml"))
+            .env("CARGO_TARGET_DIR", target_dir)
+            .env("CARGO_ENCODED_RUSTDOCFLAGS", rustdoc_flags)
+            .env("RUSTUP_HOME", rustup_home)
+            .env("CARGO_HOME", cargo_home)
+            .env("RUST_TEST_THREADS", opts.cpus_per_job.to_string())
+            .env("RUST_BACKTRACE", "0")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let output = wait_with_timeout(&mut child, timeout);
+        tests.own_doctests_passed = passed(&output);
+    }
+    Ok(artifacts)
+}
+
+/// Tests libtest reported as passing. Doctest names contain spaces
+/// (`src/lib.rs - f (line 3)`), so whole lines are matched.
+fn passed(output: &ProcessOutput) -> usize {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| l.starts_with("test ") && l.ends_with(" ... ok"))
+        .count()
+}
+
+/// Fenced examples in doc comments that rustdoc would run or compile: Rust
+/// or untagged blocks that aren't `ignore`d.
+fn count_doctests(docs: &[String]) -> usize {
+    const RUST_ATTRS: [&str; 5] = ["rust", "should_panic", "no_run", "compile_fail", ""];
+    let mut count = 0;
+    let mut open = false;
+    for line in docs.iter().flat_map(|d| d.lines()) {
+        let Some(info) = line.trim().strip_prefix("```") else {
+            continue;
+        };
+        if open {
+            open = false;
+            continue;
+        }
+        open = true;
+        let is_test = info
+            .split([',', ' '])
+            .map(str::trim)
+            .all(|attr| RUST_ATTRS.contains(&attr) || attr.starts_with("edition"));
+        if is_test {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[derive(Default)]
+struct Counter {
+    tests: usize,
+    /// Doc comment lines of non-test items, in source order.
+    docs: Vec<String>,
+}
+
+impl<'ast> Visit<'ast> for Counter {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        if node.attrs.iter().any(|a| a.path().is_ident("test")) {
+            self.tests += 1;
+        }
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        // rustdoc doesn't see `#[cfg(test)]` modules, but their tests count.
+        if is_test_code(&node.attrs) {
+            let docs = std::mem::take(&mut self.docs);
+            visit::visit_item_mod(self, node);
+            self.docs = docs;
+        } else {
+            visit::visit_item_mod(self, node);
+        }
+    }
+
+    fn visit_attribute(&mut self, attr: &'ast syn::Attribute) {
+        if !attr.path().is_ident("doc") {
+            return;
+        }
+        if let syn::Meta::NameValue(nv) = &attr.meta {
+            if let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(s),
+                ..
+            }) = &nv.value
+            {
+                self.docs.push(s.value());
+            }
+        }
+    }
+}
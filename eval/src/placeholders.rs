@@ -0,0 +1,321 @@
+//! Detection of incomplete implementations: `todo!()`/`unimplemented!()`,
+//! comments standing in for code (`// ... rest of implementation`,
+//! `// TODO`) and stubbed functions that ignore their arguments and return
+//! a default.
+//!
+//! Macros and stubs are found in the syntax tree, skipping test code.
+//! Comments never reach the tree, so they are taken from the gaps between
+//! tokens, which hold nothing but whitespace and comments, outside the test
+//! code the tree walk skipped.
+
+use crate::metrics::is_test_code;
+use proc_macro2::{TokenStream, TokenTree};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::ops::Range;
+use std::sync::OnceLock;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, Lit, Stmt, Token};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaceholderKind {
+    /// `todo!()`.
+    Todo,
+    /// `unimplemented!()`.
+    Unimplemented,
+    /// A comment in place of code: `// ...`, `// rest of implementation`,
+    /// `// omitted for brevity`.
+    ElidedCode,
+    /// `// TODO` or `// FIXME`.
+    TodoComment,
+    /// A free function or inherent method with arguments and a return value
+    /// whose body ignores the arguments and returns a default (`0`, `false`,
+    /// `None`, `Vec::new()`, …). Trait impls are left out: an empty `drop`
+    /// or a constant answer is often what the trait asks for.
+    StubBody,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Placeholders {
+    pub placeholder_count: usize,
+    /// Distinct kinds found.
+    pub placeholder_kinds: BTreeSet<PlaceholderKind>,
+}
+
+impl Placeholders {
+    fn add(&mut self, kind: PlaceholderKind) {
+        self.placeholder_count += 1;
+        self.placeholder_kinds.insert(kind);
+    }
+}
+
+pub fn detect(code: &str) -> Placeholders {
+    let mut found = Placeholders::default();
+
+    let mut test_code = Vec::new();
+    if let Ok(file) = syn::parse_file(code) {
+        let mut visitor = StubVisitor {
+            found: &mut found,
+            test_code: &mut test_code,
+            in_trait_impl: false,
+        };
+        visitor.visit_file(&file);
+    }
+
+    let comments = match code.parse::<TokenStream>() {
+        Ok(tokens) => {
+            let mut ranges = Vec::new();
+            token_ranges(tokens, &mut ranges);
+            comments_between(code, ranges, &test_code)
+        }
+        // Unbalanced delimiters: fall back to line comments.
+        Err(_) => code
+            .lines()
+            .filter_map(|l| l.trim_start().strip_prefix("//"))
+            .map(str::to_string)
+            .collect(),
+    };
+    for comment in &comments {
+        if let Some(kind) = classify_comment(comment) {
+            found.add(kind);
+        }
+    }
+    found
+}
+
+// ── Comments ────────────────────────────────────────────────────────
+
+/// Byte ranges of every token, group delimiters included.
+fn token_ranges(tokens: TokenStream, ranges: &mut Vec<Range<usize>>) {
+    for tt in tokens {
+        match tt {
+            TokenTree::Group(g) => {
+                ranges.push(g.span_open().byte_range());
+                token_ranges(g.stream(), ranges);
+                ranges.push(g.span_close().byte_range());
+            }
+            tt => ranges.push(tt.span().byte_range()),
+        }
+    }
+}
+
+/// The text of each `//` and `/* */` comment in the gaps between tokens,
+/// except those inside `skip`. Doc comments are tokens, so they are not
+/// included.
+fn comments_between(
+    code: &str,
+    mut ranges: Vec<Range<usize>>,
+    skip: &[Range<usize>],
+) -> Vec<String> {
+    ranges.sort_by_key(|r| r.start);
+    let mut comments = Vec::new();
+    let mut pos = 0;
+    let end_of_code = code.len()..code.len();
+    for range in ranges.into_iter().chain(std::iter::once(end_of_code)) {
+        if range.start > pos {
+            let mut offset = pos;
+            let mut gap = code.get(pos..range.start).unwrap_or_default();
+            while let Some(i) = gap.find("//").into_iter().chain(gap.find("/*")).min() {
+                let rest = &gap[i + 2..];
+                let (end, terminator) = if gap[i..].starts_with("//") {
+                    (rest.find('\n').unwrap_or(rest.len()), 1)
+                } else {
+                    (rest.find("*/").unwrap_or(rest.len()), 2)
+                };
+                if !skip.iter().any(|r| r.contains(&(offset + i))) {
+                    comments.push(rest[..end].to_string());
+                }
+                offset += i + 2 + end + terminator;
+                gap = rest.get(end + terminator..).unwrap_or_default();
+            }
+        }
+        pos = pos.max(range.end);
+    }
+    comments
+}
+
+fn classify_comment(text: &str) -> Option<PlaceholderKind> {
+    static ELIDED: OnceLock<Regex> = OnceLock::new();
+    static TODO: OnceLock<Regex> = OnceLock::new();
+    let elided = ELIDED.get_or_init(|| {
+        Regex::new(
+            r"(?ix)^\s*(\.{3}|…)
+            | rest\ of\ (the\ )?(implementation|code|methods?|logic|functions?)
+            | (code|implementation|logic)\ (goes\ )?here
+            | (omitted|elided|skipped)\ for\ brevity
+            | same\ as\ (before|above)
+            | (left\ )?as\ an\ exercise",
+        )
+        .expect("valid regex")
+    });
+    let todo = TODO.get_or_init(|| Regex::new(r"(?i)\b(todo|fixme)\b").expect("valid regex"));
+    if elided.is_match(text) {
+        Some(PlaceholderKind::ElidedCode)
+    } else if todo.is_match(text) {
+        Some(PlaceholderKind::TodoComment)
+    } else {
+        None
+    }
+}
+
+// ── Macros and stubs ────────────────────────────────────────────────
+
+struct StubVisitor<'a> {
+    found: &'a mut Placeholders,
+    /// Byte ranges of the test code skipped.
+    test_code: &'a mut Vec<Range<usize>>,
+    in_trait_impl: bool,
+}
+
+impl StubVisitor<'_> {
+    fn function(&mut self, sig: &syn::Signature, block: &syn::Block) {
+        let returns_value = match &sig.output {
+            syn::ReturnType::Default => false,
+            syn::ReturnType::Type(_, ty) => {
+                !matches!(&**ty, syn::Type::Tuple(t) if t.elems.is_empty())
+            }
+        };
+        if sig.inputs.is_empty() || !returns_value {
+            return;
+        }
+        let params: HashSet<String> = sig
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::FnArg::Receiver(_) => Some("self".to_string()),
+                syn::FnArg::Typed(pat) => match &*pat.pat {
+                    syn::Pat::Ident(id) => Some(id.ident.to_string()),
+                    _ => None,
+                },
+            })
+            .collect();
+        let is_stub = match block.stmts.as_slice() {
+            [Stmt::Expr(expr, None)] => is_default_value(expr),
+            _ => false,
+        };
+        if is_stub && !mentions_any(block, &params) {
+            self.found.add(PlaceholderKind::StubBody);
+        }
+    }
+}
+
+/// An expression a stub returns in place of a real result.
+fn is_default_value(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Int(i) => i.base10_digits() == "0",
+            Lit::Float(f) => f.base10_digits().trim_end_matches('0').trim_end_matches('.') == "0",
+            Lit::Bool(b) => !b.value,
+            Lit::Str(s) => s.value().is_empty(),
+            _ => false,
+        },
+        Expr::Path(p) => p.path.is_ident("None"),
+        Expr::Tuple(t) => t.elems.is_empty(),
+        Expr::Call(call) if call.args.is_empty() => {
+            let Expr::Path(func) = &*call.func else {
+                return false;
+            };
+            func.path
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "new" || s.ident == "default")
+        }
+        Expr::Call(call) if call.args.len() == 1 => {
+            // `Ok(())`, `Some(0)`, `Ok(Vec::new())`
+            matches!(&*call.func, Expr::Path(p) if p.path.is_ident("Ok") || p.path.is_ident("Some"))
+                && call.args.first().is_some_and(is_default_value)
+        }
+        Expr::Macro(m) => m.mac.path.is_ident("vec") && m.mac.tokens.is_empty(),
+        _ => false,
+    }
+}
+
+/// Whether any identifier in `block`, macro bodies included, is one of
+/// `names`.
+fn mentions_any(block: &syn::Block, names: &HashSet<String>) -> bool {
+    struct Mentions<'a> {
+        names: &'a HashSet<String>,
+        hit: bool,
+    }
+    impl<'ast> Visit<'ast> for Mentions<'_> {
+        fn visit_ident(&mut self, ident: &'ast proc_macro2::Ident) {
+            self.hit |= self.names.contains(&ident.to_string());
+        }
+        fn visit_macro(&mut self, node: &'ast syn::Macro) {
+            self.hit |= tokens_mention(node.tokens.clone(), self.names);
+            visit::visit_macro(self, node);
+        }
+    }
+    let mut mentions = Mentions { names, hit: false };
+    mentions.visit_block(block);
+    mentions.hit
+}
+
+fn tokens_mention(tokens: TokenStream, names: &HashSet<String>) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(id) => names.contains(&id.to_string()),
+        TokenTree::Group(g) => tokens_mention(g.stream(), names),
+        _ => false,
+    })
+}
+
+impl<'ast> Visit<'ast> for StubVisitor<'_> {
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        if is_test_code(&node.attrs) {
+            self.test_code.push(node.span().byte_range());
+            return;
+        }
+        visit::visit_item_mod(self, node);
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        if is_test_code(&node.attrs) {
+            self.test_code.push(node.span().byte_range());
+            return;
+        }
+        let in_trait_impl = std::mem::replace(&mut self.in_trait_impl, false);
+        self.function(&node.sig, &node.block);
+        visit::visit_item_fn(self, node);
+        self.in_trait_impl = in_trait_impl;
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        if is_test_code(&node.attrs) {
+            self.test_code.push(node.span().byte_range());
+            return;
+        }
+        if !self.in_trait_impl {
+            self.function(&node.sig, &node.block);
+        }
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        if is_test_code(&node.attrs) {
+            self.test_code.push(node.span().byte_range());
+            return;
+        }
+        let in_trait_impl = std::mem::replace(&mut self.in_trait_impl, node.trait_.is_some());
+        visit::visit_item_impl(self, node);
+        self.in_trait_impl = in_trait_impl;
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        if node.path.is_ident("todo") {
+            self.found.add(PlaceholderKind::Todo);
+        } else if node.path.is_ident("unimplemented") {
+            self.found.add(PlaceholderKind::Unimplemented);
+        }
+        // As in `metrics`: look inside expression-like macros.
+        if let Ok(args) = node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        visit::visit_macro(self, node);
+    }
+}
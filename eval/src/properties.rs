@@ -0,0 +1,165 @@
+//! Differential property testing against the task's reference solution
+//! (`tasks/solutions/<id>`). A task opts in with a `[task.properties]` table
+//! (see `PropertySpec`): seeded random inputs are run through both the
+//! candidate and the reference, and the first input on which they disagree
+//! is shrunk and reported.
+//!
+//! The harness (see `reference`) is `harness/rng.rs` and
+//! `harness/properties.rs` followed by the task's snippets, and prints one
+//! JSON report.
+
+use crate::{cap, cargo_build, reference, run_test_binary, sandbox, EvalOptions, PropertySpec};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const PRELUDE: &str = include_str!("../harness/properties.rs");
+
+/// Bytes kept of each counterexample field.
+const MAX_FIELD_BYTES: usize = 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyStatus {
+    /// The task has no property test, or the library didn't compile.
+    #[default]
+    NotRun,
+    /// Every input gave the reference's output.
+    Agreed,
+    Diverged,
+    /// The harness didn't compile against the candidate, whose API differs
+    /// from the reference's.
+    CompileError,
+    Timeout,
+    /// The harness died without a report: killed by a signal (e.g. on stack
+    /// overflow) or exited early.
+    Crash,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Differential {
+    pub property_status: PropertyStatus,
+    /// Valid inputs run, up to and including the first diverging one.
+    pub property_cases: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub property_counterexample: Option<Counterexample>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Counterexample {
+    /// Index and seed of the first diverging input.
+    pub case: usize,
+    pub seed: u64,
+    /// The input as generated and after shrinking, printed with `{:?}`.
+    pub input: String,
+    pub shrunk_input: String,
+    /// The reference's and the candidate's output on the shrunk input:
+    /// `{:?}` of the task's `call`, or `panicked: <message>`.
+    pub expected: String,
+    pub actual: String,
+}
+
+/// The harness's JSON report.
+#[derive(Deserialize)]
+struct Report {
+    cases: usize,
+    counterexample: Option<Counterexample>,
+}
+
+/// Build the harness into the temp crate at `crate_dir`, whose library has
+/// already been built and which has the reference added, and run it under
+/// `time_limit`. Returns the result and the files written to the shared
+/// target dir.
+pub fn run(
+    spec: &PropertySpec,
+    crate_dir: &Path,
+    time_limit: Duration,
+    opts: &EvalOptions,
+    target_dir: &Path,
+) -> std::io::Result<(Differential, Vec<PathBuf>)> {
+    reference::add_target(crate_dir, "properties", &harness(spec))?;
+    let build = cargo_build(crate_dir, &["properties"], opts.compile_timeout, opts, target_dir)?;
+    let mut differential = Differential::default();
+    if build.timed_out {
+        return Ok((differential, build.artifacts));
+    }
+    let Some(exe) = build.executables.get("properties").filter(|_| build.success) else {
+        differential.property_status = PropertyStatus::CompileError;
+        return Ok((differential, build.artifacts));
+    };
+
+    let output = run_test_binary(exe, time_limit, opts)?;
+    let report = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .rev()
+        .find_map(|line| serde_json::from_str::<Report>(line).ok());
+    differential.property_status = match (&report, output.signal) {
+        _ if output.timed_out => PropertyStatus::Timeout,
+        (_, Some(sig)) if sandbox::is_cpu_limit(sig) => PropertyStatus::Timeout,
+        (None, _) | (_, Some(_)) => PropertyStatus::Crash,
+        (Some(r), None) if r.counterexample.is_some() => PropertyStatus::Diverged,
+        (Some(_), None) => PropertyStatus::Agreed,
+    };
+    if let Some(report) = report {
+        differential.property_cases = report.cases;
+        differential.property_counterexample = report.counterexample.map(|c| Counterexample {
+            input: cap(&c.input, MAX_FIELD_BYTES),
+            shrunk_input: cap(&c.shrunk_input, MAX_FIELD_BYTES),
+            expected: cap(&c.expected, MAX_FIELD_BYTES),
+            actual: cap(&c.actual, MAX_FIELD_BYTES),
+            ..c
+        });
+    }
+    Ok((differential, build.artifacts))
+}
+
+/// The preludes followed by the task's snippets.
+fn harness(spec: &PropertySpec) -> String {
+    let PropertySpec {
+        input,
+        generate,
+        call,
+        valid,
+        helpers,
+        cases,
+        max_size,
+        seed,
+    } = spec;
+    let valid = valid.as_deref().unwrap_or("true");
+    let rng = reference::RNG;
+    format!(
+        "{rng}{PRELUDE}
+// ── Task ────────────────────────────────────────────────────────────
+
+const CASES: usize = {cases};
+const MAX_SIZE: usize = {max_size};
+const SEED: u64 = {seed};
+
+{helpers}
+
+type Input = {input};
+
+fn generate(rng: &mut Rng, size: usize) -> Input {{
+{generate}
+}}
+
+fn valid(input: &Input) -> bool {{
+{valid}
+}}
+
+fn expected(input: &Input) -> String {{
+    use reference as sol;
+    format!(\"{{:?}}\", {{
+{call}
+    }})
+}}
+
+fn actual(input: &Input) -> String {{
+    use solution as sol;
+    format!(\"{{:?}}\", {{
+{call}
+    }})
+}}
+"
+    )
+}
@@ -0,0 +1,42 @@
+//! Harnesses that run the candidate beside the task's reference solution
+//! (`tasks/solutions/<id>`): the property test and the benchmark. Each is a
+//! test target without libtest in the candidate's temp crate, which links
+//! the candidate as `solution` and the reference as `reference`.
+
+use crate::EDITION;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// `harness/rng.rs`, for harnesses that make random inputs.
+pub const RNG: &str = include_str!("../harness/rng.rs");
+
+/// Add the reference solution to the temp crate at `crate_dir` as a
+/// dev-dependency, so the library built for the task's tests is reused.
+pub fn add(crate_dir: &Path, code: &str) -> io::Result<()> {
+    let dir = crate_dir.join("reference");
+    fs::create_dir_all(dir.join("src"))?;
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!("[package]\nname = \"reference\"\nversion = \"0.1.0\"\nedition = \"{EDITION}\"\n"),
+    )?;
+    fs::write(dir.join("src/lib.rs"), code)?;
+    append_manifest(crate_dir, "\n[dev-dependencies]\nreference = { path = \"reference\" }\n")
+}
+
+/// Add `tests/<name>.rs` with `source` as a test target without libtest.
+/// Lints are allowed, since the harness isn't the candidate's code.
+pub fn add_target(crate_dir: &Path, name: &str, source: &str) -> io::Result<()> {
+    fs::write(
+        crate_dir.join("tests").join(format!("{name}.rs")),
+        format!("#![allow(unused)]\n\n{source}"),
+    )?;
+    append_manifest(crate_dir, &format!("\n[[test]]\nname = \"{name}\"\nharness = false\n"))
+}
+
+fn append_manifest(crate_dir: &Path, text: &str) -> io::Result<()> {
+    let path = crate_dir.join("Cargo.toml");
+    let mut manifest = fs::read_to_string(&path)?;
+    manifest.push_str(text);
+    fs::write(path, manifest)
+}
@@ -0,0 +1,105 @@
+//! Formatting conformance: whether a solution is already in rustfmt's
+//! default style, and how large the diff to get there would be.
+//!
+//! rustfmt runs with the edition the candidate crate is built with and an
+//! empty config, on a copy of the solution.
+
+use crate::{sandbox, wait_with_timeout, EDITION};
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+use std::fs;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// rustfmt is fast on anything a model writes; this only guards against a
+/// pathological input hanging the job.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FmtStatus {
+    /// rustfmt would leave the file unchanged.
+    Formatted,
+    Unformatted,
+    /// rustfmt couldn't parse the code.
+    ParseError,
+    /// rustfmt is missing, or failed for reasons unrelated to the code.
+    #[default]
+    NotChecked,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Formatting {
+    pub fmt_status: FmtStatus,
+    /// Lines in rustfmt's diff: lines it would remove plus lines it would
+    /// add. Zero unless `fmt_status` is `unformatted`.
+    pub fmt_diff_lines: usize,
+}
+
+/// Whether `rustfmt` is installed.
+pub fn available() -> bool {
+    Command::new("rustfmt")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+pub fn check(code: &str) -> Formatting {
+    // Extraction drops the final newline, which rustfmt would add back.
+    let code = if code.ends_with('\n') {
+        code.to_string()
+    } else {
+        format!("{code}\n")
+    };
+    let not_checked = Formatting::default();
+    let Ok(dir) = TempDir::new() else {
+        return not_checked;
+    };
+    let file = dir.path().join("lib.rs");
+    let config = dir.path().join("rustfmt.toml");
+    if fs::write(&file, &code).is_err() || fs::write(&config, "").is_err() {
+        return not_checked;
+    }
+
+    let mut cmd = Command::new("rustfmt");
+    sandbox::own_process_group(&mut cmd)
+        .args(["--edition", EDITION])
+        .arg("--config-path")
+        .arg(&config)
+        .arg(&file)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let Ok(mut child) = cmd.spawn() else {
+        return not_checked;
+    };
+    let output = wait_with_timeout(&mut child, TIMEOUT);
+    if output.timed_out || output.signal.is_some() {
+        return not_checked;
+    }
+    if !output.success {
+        // rustfmt exits with 1 when the input doesn't parse.
+        return Formatting {
+            fmt_status: FmtStatus::ParseError,
+            fmt_diff_lines: 0,
+        };
+    }
+    let Ok(formatted) = fs::read_to_string(&file) else {
+        return not_checked;
+    };
+
+    let diff_lines = TextDiff::from_lines(&code, &formatted)
+        .iter_all_changes()
+        .filter(|c| c.tag() != ChangeTag::Equal)
+        .count();
+    Formatting {
+        fmt_status: if diff_lines == 0 {
+            FmtStatus::Formatted
+        } else {
+            FmtStatus::Unformatted
+        },
+        fmt_diff_lines: diff_lines,
+    }
+}
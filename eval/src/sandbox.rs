@@ -0,0 +1,256 @@
+//! Confinement for model-written code.
+//!
+//! Every child eval spawns gets its own process group, so a timeout kills
+//! the whole tree rather than just its leader. Candidate test binaries are
+//! further run with rlimits on CPU time, address space, file size and
+//! process count, in a fresh temp dir that is also their `HOME` and
+//! `TMPDIR`, and, on Linux where unprivileged namespaces are available,
+//! inside an empty network namespace. With several jobs, each worker
+//! thread is pinned to its own CPUs, which everything it starts inherits.
+
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+/// Resource limits for one candidate test binary.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// CPU time across all threads.
+    pub cpu: Duration,
+    pub memory_bytes: u64,
+    pub file_size_bytes: u64,
+    /// Processes and threads. Linux counts this per user rather than per
+    /// tree, so it has to leave room for everything else the user runs.
+    pub max_processes: u64,
+}
+
+/// Put `cmd` in a new process group so `kill_tree` reaches all of it.
+pub fn own_process_group(cmd: &mut Command) -> &mut Command {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(cmd, 0);
+    cmd
+}
+
+/// Configure `cmd` to run untrusted code: own process group, rlimits, a
+/// private working/temp dir and, where possible, no network.
+pub fn confine<'a>(cmd: &'a mut Command, limits: Limits, scratch: &Path) -> &'a mut Command {
+    own_process_group(cmd)
+        .current_dir(scratch)
+        .env("HOME", scratch)
+        .env("TMPDIR", scratch);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let netns = network_isolation();
+        // SAFETY: the closure runs in the forked child before exec and only
+        // makes async-signal-safe syscalls.
+        unsafe {
+            cmd.pre_exec(move || {
+                set_limit(libc::RLIMIT_CPU as _, limits.cpu.as_secs().max(1), 1)?;
+                set_limit(libc::RLIMIT_AS as _, limits.memory_bytes, 0)?;
+                set_limit(libc::RLIMIT_FSIZE as _, limits.file_size_bytes, 0)?;
+                // Fail oversized writes with EFBIG instead of killing the
+                // whole test binary with SIGXFSZ.
+                libc::signal(libc::SIGXFSZ, libc::SIG_IGN);
+                set_limit(libc::RLIMIT_NPROC as _, limits.max_processes, 0)?;
+                if let Some(flags) = netns {
+                    unshare(flags)?;
+                }
+                Ok(())
+            });
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = limits;
+    cmd
+}
+
+/// Processes and threads currently owned by this user, which the
+/// per-user process limit has to allow for on top of the candidate's own.
+pub fn user_task_count() -> u64 {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: getuid cannot fail.
+        let uid = unsafe { libc::getuid() }.to_string();
+        let Ok(entries) = std::fs::read_dir("/proc") else {
+            return 0;
+        };
+        entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| std::fs::read_to_string(e.path().join("status")).ok())
+            .filter(|status| {
+                status
+                    .lines()
+                    .find_map(|l| l.strip_prefix("Uid:"))
+                    .and_then(|ids| ids.split_whitespace().next())
+                    == Some(uid.as_str())
+            })
+            .filter_map(|status| {
+                status
+                    .lines()
+                    .find_map(|l| l.strip_prefix("Threads:"))
+                    .and_then(|n| n.trim().parse::<u64>().ok())
+            })
+            .sum()
+    }
+    #[cfg(not(target_os = "linux"))]
+    0
+}
+
+/// Whether `child` has exited, without reaping it. Until it is reaped its
+/// pid, which is also its group id, can't be reused, so `kill_tree` can
+/// still reach the stragglers it left behind and nothing else.
+pub fn has_exited(child: &mut std::process::Child) -> std::io::Result<bool> {
+    #[cfg(unix)]
+    {
+        // SAFETY: `info` is a valid, zeroed siginfo_t; with WNOHANG the kernel
+        // leaves `si_pid` at 0 when the child is still running.
+        unsafe {
+            let mut info: libc::siginfo_t = std::mem::zeroed();
+            let options = libc::WEXITED | libc::WNOHANG | libc::WNOWAIT;
+            if libc::waitid(libc::P_PID, child.id() as libc::id_t, &mut info, options) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(info.si_pid() != 0)
+        }
+    }
+    #[cfg(not(unix))]
+    child.try_wait().map(|status| status.is_some())
+}
+
+/// SIGKILL every process in the group led by `child`. Call it before the
+/// leader is reaped (see `has_exited`), while the group id is still its own.
+pub fn kill_tree(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    {
+        // SAFETY: plain syscall; the group id is the leader's pid.
+        unsafe {
+            libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+}
+
+/// CPUs the calling thread may run on, in ascending order; empty where
+/// affinity isn't supported.
+pub fn allowed_cpus() -> Vec<usize> {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: an all-zero cpu_set_t is a valid empty set, and the kernel
+        // writes at most `size_of::<cpu_set_t>()` bytes into it.
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+                return Vec::new();
+            }
+            (0..libc::CPU_SETSIZE as usize)
+                .filter(|&cpu| libc::CPU_ISSET(cpu, &set))
+                .collect()
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    Vec::new()
+}
+
+/// Restrict the calling thread to `cpus`. Threads and processes it starts
+/// afterwards inherit the mask, so a worker pinned this way keeps its
+/// builds, test binaries and helper threads on its own CPUs.
+pub fn pin_thread(cpus: &[usize]) -> std::io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: as in `allowed_cpus`; pid 0 is the calling thread.
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            for &cpu in cpus {
+                libc::CPU_SET(cpu, &mut set);
+            }
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = cpus;
+    Ok(())
+}
+
+/// Whether a test binary killed by `signal` ran out of CPU time, which is a
+/// hang rather than a crash.
+pub fn is_cpu_limit(signal: i32) -> bool {
+    #[cfg(unix)]
+    return signal == libc::SIGXCPU;
+    #[cfg(not(unix))]
+    {
+        let _ = signal;
+        false
+    }
+}
+
+/// The `unshare` flags that give a child an empty network namespace, or
+/// `None` if this system doesn't allow it. Probed once per process.
+pub fn network_isolation() -> Option<i32> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::process::CommandExt;
+        use std::sync::OnceLock;
+        static FLAGS: OnceLock<Option<i32>> = OnceLock::new();
+        *FLAGS.get_or_init(|| {
+            // A new user namespace lets unprivileged users create the network
+            // namespace; root can do without one.
+            [
+                libc::CLONE_NEWUSER | libc::CLONE_NEWNET,
+                libc::CLONE_NEWNET,
+            ]
+            .into_iter()
+            .find(|&flags| {
+                let mut probe = Command::new("true");
+                // SAFETY: as in `confine`.
+                unsafe {
+                    probe.pre_exec(move || unshare(flags));
+                }
+                probe.status().is_ok_and(|s| s.success())
+            })
+        })
+    }
+    #[cfg(not(target_os = "linux"))]
+    None
+}
+
+/// Lower the soft limit to `value` and the hard limit to `value + grace`,
+/// each capped at the existing hard limit (raising it would fail). For CPU
+/// time the hard limit must be above the soft one: Linux sends SIGXCPU only
+/// at the soft limit, and if both are equal it kills with SIGKILL instead,
+/// which `is_cpu_limit` can't tell from any other kill.
+#[cfg(unix)]
+fn set_limit(resource: i32, value: u64, grace: u64) -> std::io::Result<()> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `limit` is a valid rlimit for the duration of both calls.
+    if unsafe { libc::getrlimit(resource as _, &mut limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let hard = (value.saturating_add(grace) as libc::rlim_t).min(limit.rlim_max);
+    limit.rlim_cur = (value as libc::rlim_t).min(hard.saturating_sub(grace as libc::rlim_t));
+    limit.rlim_max = hard;
+    if unsafe { libc::setrlimit(resource as _, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn unshare(flags: i32) -> std::io::Result<()> {
+    // SAFETY: plain syscall with no pointer arguments.
+    if unsafe { libc::unshare(flags) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn unshare(_flags: i32) -> std::io::Result<()> {
+    Ok(())
+}
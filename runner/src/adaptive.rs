@@ -0,0 +1,355 @@
+//! Adaptive sequential sampling.
+//!
+//! After the fixed `--repetitions` sweep, every task × style group is scored
+//! with eval's compile-and-test logic (the eval binary is invoked on just the
+//! new responses) and sampled again only while the 95% Wilson interval on its
+//! mean pass rate is wider than the target. Unlike 1.96·s/√n, that interval
+//! never collapses to zero width when the first runs agree. Sampling stops at `max_runs` per group. Each stopping decision is
+//! written to `adaptive.json`, because data-dependent sample sizes have to be
+//! accounted for when pooling runs across groups.
+
+use crate::events::Shutdown;
+use crate::experiment::Experiment;
+use crate::{chrono_now, Cell, Sweep};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tracing::info;
+
+type BoxError = Box<dyn std::error::Error>;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct AdaptiveConfig {
+    pub max_runs: u32,
+    pub target_ci: f64,
+}
+
+/// The fields of eval's `EvalResult` that sampling decisions need.
+#[derive(Deserialize)]
+struct EvalRecord {
+    task_id: String,
+    style: String,
+    run: u32,
+    compiled: bool,
+    tests_total: usize,
+    tests_passed: usize,
+}
+
+impl EvalRecord {
+    /// Fraction of tests passed, as computed by analysis.
+    fn pass(&self) -> f64 {
+        if self.compiled && self.tests_total > 0 {
+            self.tests_passed as f64 / self.tests_total as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum Decision {
+    /// The interval reached the target width.
+    Converged,
+    /// `max_runs` was reached first.
+    Cap,
+    /// The runner was stopped before a decision was reached.
+    Interrupted,
+}
+
+#[derive(Serialize)]
+struct GroupDecision {
+    task_id: String,
+    style: String,
+    runs: usize,
+    pass_mean: f64,
+    ci_half_width: f64,
+    decision: Decision,
+    round: u32,
+}
+
+#[derive(Serialize)]
+struct AdaptiveReport {
+    updated_at: String,
+    config: AdaptiveConfig,
+    min_runs: u32,
+    groups: Vec<GroupDecision>,
+}
+
+type GroupKey = (String, String);
+
+/// Sample groups until each has converged or hit the cap. Returns `false`
+/// if the run was interrupted.
+pub async fn run(
+    sweep: &mut Sweep<'_>,
+    root: &Path,
+    experiment: &Experiment,
+    config: AdaptiveConfig,
+    min_runs: u32,
+    groups: &[GroupKey],
+) -> Result<bool, BoxError> {
+    let wanted: HashSet<&GroupKey> = groups.iter().collect();
+    let mut scores: BTreeMap<GroupKey, BTreeMap<u32, f64>> = BTreeMap::new();
+    let mut attempts: BTreeMap<GroupKey, u32> = BTreeMap::new();
+    let mut decided: BTreeMap<GroupKey, GroupDecision> = BTreeMap::new();
+
+    let mut to_evaluate: Vec<Cell> = experiment
+        .completed_cells()?
+        .into_iter()
+        .filter(|c| wanted.contains(&(c.task_id.clone(), c.style.clone())))
+        .collect();
+    for cell in &to_evaluate {
+        let n = attempts
+            .entry((cell.task_id.clone(), cell.style.clone()))
+            .or_default();
+        *n = (*n).max(cell.run);
+    }
+
+    let mut round = 0;
+    loop {
+        round += 1;
+        let Some(records) = evaluate(root, experiment, &to_evaluate, sweep.shutdown).await? else {
+            mark_interrupted(groups, &scores, &mut decided, round);
+            write_report(experiment, config, min_runs, decided)?;
+            return Ok(false);
+        };
+        for record in records {
+            scores
+                .entry((record.task_id.clone(), record.style.clone()))
+                .or_default()
+                .insert(record.run, record.pass());
+        }
+
+        let mut next: Vec<Cell> = Vec::new();
+        for key in groups {
+            if decided.contains_key(key) {
+                continue;
+            }
+            let values: Vec<f64> = scores
+                .get(key)
+                .map(|runs| runs.values().copied().collect())
+                .unwrap_or_default();
+            let (mean, half_width) = mean_and_half_width(&values);
+            let attempted = attempts.get(key).copied().unwrap_or(0);
+
+            let decision = if values.len() >= min_runs as usize && half_width <= config.target_ci {
+                Some(Decision::Converged)
+            } else if attempted >= config.max_runs {
+                Some(Decision::Cap)
+            } else {
+                None
+            };
+
+            match decision {
+                Some(decision) => {
+                    let group = GroupDecision {
+                        task_id: key.0.clone(),
+                        style: key.1.clone(),
+                        runs: values.len(),
+                        pass_mean: mean,
+                        ci_half_width: half_width,
+                        decision,
+                        round,
+                    };
+                    sweep
+                        .events
+                        .emit("adaptive_decision", None, serde_json::to_value(&group)?);
+                    decided.insert(key.clone(), group);
+                }
+                None => {
+                    // Take the lowest run number without a saved response, so
+                    // failed requests are filled in rather than skipped.
+                    let run = (1..)
+                        .find(|r| {
+                            !experiment.has_result(&Cell {
+                                task_id: key.0.clone(),
+                                style: key.1.clone(),
+                                run: *r,
+                            })
+                        })
+                        .unwrap_or(1);
+                    next.push(Cell {
+                        task_id: key.0.clone(),
+                        style: key.1.clone(),
+                        run,
+                    });
+                    attempts.insert(key.clone(), attempted + 1);
+                }
+            }
+        }
+
+        if next.is_empty() {
+            break;
+        }
+        info!(
+            "Adaptive round {}: {} group(s) decided, sampling {} more",
+            round,
+            decided.len(),
+            next.len()
+        );
+        sweep.progress.add_total(next.len());
+        let completed = sweep.run_cells(&next).await;
+        to_evaluate = next
+            .into_iter()
+            .filter(|c| experiment.has_result(c))
+            .collect();
+
+        if !completed {
+            mark_interrupted(groups, &scores, &mut decided, round);
+            write_report(experiment, config, min_runs, decided)?;
+            return Ok(false);
+        }
+    }
+
+    let converged = decided
+        .values()
+        .filter(|d| d.decision == Decision::Converged)
+        .count();
+    info!(
+        "Adaptive sampling finished after {} round(s): {} converged, {} capped",
+        round,
+        converged,
+        decided.len() - converged
+    );
+    write_report(experiment, config, min_runs, decided)?;
+    Ok(true)
+}
+
+/// Record every undecided group as interrupted in `round`.
+fn mark_interrupted(
+    groups: &[GroupKey],
+    scores: &BTreeMap<GroupKey, BTreeMap<u32, f64>>,
+    decided: &mut BTreeMap<GroupKey, GroupDecision>,
+    round: u32,
+) {
+    for key in groups {
+        if decided.contains_key(key) {
+            continue;
+        }
+        let values: Vec<f64> = scores
+            .get(key)
+            .map(|runs| runs.values().copied().collect())
+            .unwrap_or_default();
+        let (mean, half_width) = mean_and_half_width(&values);
+        decided.insert(
+            key.clone(),
+            GroupDecision {
+                task_id: key.0.clone(),
+                style: key.1.clone(),
+                runs: values.len(),
+                pass_mean: mean,
+                ci_half_width: half_width,
+                decision: Decision::Interrupted,
+                round,
+            },
+        );
+    }
+}
+
+/// Mean and 95% Wilson score interval half-width, with the mean pass rate
+/// taken as the proportion of passes. The width stays positive for any
+/// finite number of runs, so identical early outcomes don't count as
+/// converged; it is infinite without samples.
+fn mean_and_half_width(values: &[f64]) -> (f64, f64) {
+    const Z: f64 = 1.96;
+    let n = values.len() as f64;
+    if values.is_empty() {
+        return (0.0, f64::INFINITY);
+    }
+    let mean = values.iter().sum::<f64>() / n;
+    let z2 = Z * Z;
+    let spread = mean * (1.0 - mean) / n + z2 / (4.0 * n * n);
+    (mean, Z * spread.sqrt() / (1.0 + z2 / n))
+}
+
+/// Score `cells` by running the eval binary on just those responses, or
+/// `None` if a shutdown signal arrived first. Eval runs in its own process
+/// group, so Ctrl-C reaches the runner only, which then kills the group.
+async fn evaluate(
+    root: &Path,
+    experiment: &Experiment,
+    cells: &[Cell],
+    shutdown: &Shutdown,
+) -> Result<Option<Vec<EvalRecord>>, BoxError> {
+    if cells.is_empty() {
+        return Ok(Some(Vec::new()));
+    }
+    let output = experiment.dir.join(".adaptive-eval.json");
+    let mut cmd = Command::new("cargo");
+    cmd.args(["run", "--release", "--quiet", "--manifest-path"])
+        .arg(root.join("eval/Cargo.toml"))
+        .arg("--")
+        .arg(&experiment.dir)
+        .arg("--output")
+        .arg(&output);
+    for cell in cells {
+        cmd.arg("--only")
+            .arg(format!("{}/{}/{}", cell.task_id, cell.style, cell.run));
+    }
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+    info!("Evaluating {} response(s)", cells.len());
+    let child = cmd.stdout(Stdio::null()).stderr(Stdio::piped()).spawn()?;
+    let pid = child.id();
+    let mut wait = tokio::task::spawn_blocking(move || child.wait_with_output());
+    let result = tokio::select! {
+        result = &mut wait => result,
+        _ = shutdown.stopping() => {
+            kill_group(pid);
+            let _ = wait.await;
+            let _ = std::fs::remove_file(&output);
+            return Ok(None);
+        }
+    };
+    let records = read_records(result?, &output);
+    let _ = std::fs::remove_file(&output);
+    records.map(Some)
+}
+
+/// Eval's records from `output`, if it succeeded.
+fn read_records(
+    result: std::io::Result<std::process::Output>,
+    output: &Path,
+) -> Result<Vec<EvalRecord>, BoxError> {
+    let result = result?;
+    if !result.status.success() {
+        return Err(format!(
+            "eval failed ({}): {}",
+            result.status,
+            String::from_utf8_lossy(&result.stderr)
+        )
+        .into());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(output)?)?)
+}
+
+/// SIGKILL the process group led by `pid`.
+fn kill_group(pid: u32) {
+    #[cfg(unix)]
+    // SAFETY: plain syscall with no pointer arguments.
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+fn write_report(
+    experiment: &Experiment,
+    config: AdaptiveConfig,
+    min_runs: u32,
+    decided: BTreeMap<GroupKey, GroupDecision>,
+) -> Result<(), BoxError> {
+    let report = AdaptiveReport {
+        updated_at: chrono_now(),
+        config,
+        min_runs,
+        groups: decided.into_values().collect(),
+    };
+    std::fs::write(
+        experiment.dir.join("adaptive.json"),
+        serde_json::to_string_pretty(&report)?,
+    )?;
+    Ok(())
+}
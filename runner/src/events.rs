@@ -0,0 +1,257 @@
+//! Run-time observability: the JSONL event log, the progress line and
+//! SIGINT/SIGTERM handling.
+
+use crate::{chrono_now, Cell};
+use serde_json::{Map, Value};
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+// ── Event log ──────────────────────────────────────────────────────────────
+
+/// Append-only `events.jsonl` with one JSON object per line. Every line is
+/// flushed immediately so the log survives a hard kill.
+pub struct EventLog {
+    file: Option<Mutex<std::fs::File>>,
+}
+
+impl EventLog {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(EventLog {
+            file: Some(Mutex::new(file)),
+        })
+    }
+
+    /// A log that discards everything (used for dry runs).
+    pub fn disabled() -> Self {
+        EventLog { file: None }
+    }
+
+    /// Record `event`, optionally tagged with a cell, plus any extra fields
+    /// (`fields` must be a JSON object or null).
+    pub fn emit(&self, event: &str, cell: Option<&Cell>, fields: Value) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let mut record = Map::new();
+        record.insert("ts".into(), chrono_now().into());
+        record.insert("event".into(), event.into());
+        if let Some(cell) = cell {
+            record.insert("task_id".into(), cell.task_id.clone().into());
+            record.insert("style".into(), cell.style.clone().into());
+            record.insert("run".into(), cell.run.into());
+        }
+        if let Value::Object(extra) = fields {
+            record.extend(extra);
+        }
+        let mut line = Value::Object(record).to_string();
+        line.push('\n');
+        if let Ok(mut f) = file.lock() {
+            if let Err(e) = f.write_all(line.as_bytes()).and_then(|_| f.flush()) {
+                warn!("Failed to write event log: {}", e);
+            }
+        }
+    }
+}
+
+// ── Progress ───────────────────────────────────────────────────────────────
+
+/// Per-million-token prices used to estimate spend.
+#[derive(Clone, Copy, Default)]
+pub struct Pricing {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+/// Tracks completed/total cells, errors, tokens and spend, and renders them
+/// as a single status line (redrawn in place when stderr is a terminal).
+pub struct Progress {
+    total: usize,
+    done: usize,
+    /// Cells finished by this invocation (excludes ones resumed from disk),
+    /// the basis for the ETA.
+    done_here: usize,
+    errors: usize,
+    input_tokens: u64,
+    output_tokens: u64,
+    pricing: Pricing,
+    started: Instant,
+    live: bool,
+}
+
+impl Progress {
+    pub fn new(total: usize, pricing: Pricing) -> Self {
+        Progress {
+            total,
+            done: 0,
+            done_here: 0,
+            errors: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            pricing,
+            started: Instant::now(),
+            live: std::io::stderr().is_terminal(),
+        }
+    }
+
+    /// Extend the plan by `n` cells (adaptive sampling adds them per round).
+    pub fn add_total(&mut self, n: usize) {
+        self.total += n;
+    }
+
+    /// A cell that was already saved by an earlier invocation.
+    pub fn skipped(&mut self) {
+        self.done += 1;
+    }
+
+    pub fn saved(&mut self, input_tokens: u64, output_tokens: u64) {
+        self.done += 1;
+        self.done_here += 1;
+        self.input_tokens += input_tokens;
+        self.output_tokens += output_tokens;
+        self.report();
+    }
+
+    pub fn failed(&mut self) {
+        self.done += 1;
+        self.done_here += 1;
+        self.errors += 1;
+        self.report();
+    }
+
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+
+    pub fn spend(&self) -> f64 {
+        (self.input_tokens as f64 * self.pricing.input_per_mtok
+            + self.output_tokens as f64 * self.pricing.output_per_mtok)
+            / 1_000_000.0
+    }
+
+    pub fn line(&self) -> String {
+        let pct = if self.total > 0 {
+            100.0 * self.done as f64 / self.total as f64
+        } else {
+            100.0
+        };
+        let eta = if self.done_here > 0 && self.done < self.total {
+            let per_cell = self.started.elapsed() / self.done_here as u32;
+            format_duration(per_cell * (self.total - self.done) as u32)
+        } else {
+            "--".to_string()
+        };
+        format!(
+            "{}/{} ({:.1}%) | errors {} | ETA {} | tokens {}in/{}out | spend ${:.2}",
+            self.done,
+            self.total,
+            pct,
+            self.errors,
+            eta,
+            self.input_tokens,
+            self.output_tokens,
+            self.spend()
+        )
+    }
+
+    fn report(&self) {
+        if self.live {
+            eprint!("\r\x1b[2K{}", self.line());
+            let _ = std::io::stderr().flush();
+        } else {
+            info!("Progress: {}", self.line());
+        }
+    }
+
+    /// Terminate the live line so subsequent output starts on a fresh line.
+    pub fn finish(&self) {
+        if self.live && self.done_here > 0 {
+            eprintln!();
+        }
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+// ── Shutdown ───────────────────────────────────────────────────────────────
+
+/// Counts SIGINT/SIGTERM deliveries. The first one asks the runner to stop
+/// after the in-flight request has been saved; the second abandons it.
+#[derive(Default)]
+pub struct Shutdown {
+    signals: AtomicUsize,
+    notify: Notify,
+}
+
+impl Shutdown {
+    /// Install the signal handlers on a background task.
+    pub fn install(self: &std::sync::Arc<Self>) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            #[cfg(unix)]
+            let mut term =
+                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                    .expect("install SIGTERM handler");
+            loop {
+                #[cfg(unix)]
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => {}
+                }
+                #[cfg(not(unix))]
+                let _ = tokio::signal::ctrl_c().await;
+
+                let n = this.signals.fetch_add(1, Ordering::SeqCst) + 1;
+                if n == 1 {
+                    warn!(
+                        "Shutdown requested: finishing the in-flight request \
+                         (signal again to abandon it)"
+                    );
+                } else {
+                    warn!("Abandoning the in-flight request");
+                }
+                this.notify.notify_waiters();
+            }
+        });
+    }
+
+    /// Whether no new cells should be started.
+    pub fn requested(&self) -> bool {
+        self.signals.load(Ordering::SeqCst) >= 1
+    }
+
+    /// Resolves once a signal asks the runner to stop.
+    pub async fn stopping(&self) {
+        self.signaled(1).await
+    }
+
+    /// Resolves once a second signal asks to abandon in-flight work.
+    pub async fn abandoned(&self) {
+        self.signaled(2).await
+    }
+
+    async fn signaled(&self, count: usize) {
+        loop {
+            let notified = self.notify.notified();
+            if self.signals.load(Ordering::SeqCst) >= count {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
@@ -0,0 +1,469 @@
+//! Per-experiment result namespaces.
+//!
+//! Every runner invocation writes into `results/{experiment_id}/`, next to a
+//! frozen copy of the configuration and prompts it was started with:
+//!
+//! ```text
+//! results/{experiment_id}/
+//!   config.json                     frozen ExperimentConfig
+//!   manifest.json                   planned / completed cells, run status
+//!   events.jsonl                    structured event log (see events.rs)
+//!   prompts/system.md               system prompt as sent
+//!   prompts/{style}.md              style templates as loaded
+//!   {task_id}/{style}/run_{N}.rs
+//!   {task_id}/{style}/run_{N}.meta.json
+//! ```
+//!
+//! Reopening an existing experiment is only allowed when the configuration
+//! and prompts are unchanged, so a rerun can resume but never overwrite data
+//! produced under different settings.
+
+use crate::adaptive::AdaptiveConfig;
+use crate::{chrono_now, Cell, RunMetadata};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+
+type BoxError = Box<dyn std::error::Error>;
+
+/// Settings that determine what an experiment measures. Two invocations with
+/// equal configs (e.g. the shards of one sweep) may share an experiment.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ExperimentConfig {
+    pub api_url: String,
+    pub model: String,
+    pub repetitions: u32,
+    pub tasks: Option<String>,
+    /// The task IDs `tasks` matched when the experiment was created, so that
+    /// merging shards expects the cells they ran; empty in older experiments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub task_ids: Vec<String>,
+    pub styles: Vec<String>,
+    pub max_tokens: u32,
+    pub temperature: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adaptive: Option<AdaptiveConfig>,
+}
+
+/// State of the last invocation that touched the experiment.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Interrupted,
+    Finished,
+    Merged,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub experiment_id: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub status: RunStatus,
+    /// Cells this invocation is responsible for: its shard's slice, or the
+    /// whole sweep.
+    pub planned_cells: usize,
+    /// Cells in the whole sweep, of which a shard plans a slice.
+    #[serde(default)]
+    pub sweep_cells: usize,
+    pub completed_cells: Vec<Cell>,
+}
+
+pub struct Experiment {
+    pub id: String,
+    pub dir: PathBuf,
+    pub config: ExperimentConfig,
+}
+
+/// Build a default experiment ID such as `20250101T120000Z-gpt-4o`.
+pub fn generate_id(model: &str) -> String {
+    let stamp: String = chrono_now()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    let model: String = model
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("{}-{}", stamp, model)
+}
+
+impl Experiment {
+    /// Create `results_root/{id}` and freeze the config and prompts into it, or
+    /// reopen it when it already exists with an identical config and prompts.
+    pub fn open_or_create(
+        results_root: &Path,
+        id: &str,
+        config: &ExperimentConfig,
+        system_prompt: &str,
+        templates: &BTreeMap<String, String>,
+    ) -> Result<Self, BoxError> {
+        let dir = results_root.join(id);
+        let experiment = Experiment {
+            id: id.to_string(),
+            dir,
+            config: config.clone(),
+        };
+
+        if experiment.dir.join("config.json").is_file() {
+            let existing = Self::load(&experiment.dir)?;
+            // Experiments from before task IDs were frozen resume on the rest.
+            let mut expected = config.clone();
+            if existing.config.task_ids.is_empty() {
+                expected.task_ids.clear();
+            }
+            if existing.config != expected {
+                return Err(format!(
+                    "Experiment '{}' already exists with a different configuration; \
+                     choose another --experiment ID",
+                    id
+                )
+                .into());
+            }
+            let mut frozen = vec![("system", system_prompt)];
+            frozen.extend(templates.iter().map(|(s, t)| (s.as_str(), t.as_str())));
+            for (name, text) in frozen {
+                let path = experiment.prompt_path(name);
+                if std::fs::read_to_string(&path).ok().as_deref() != Some(text) {
+                    return Err(format!(
+                        "Experiment '{}' was started with a different {} prompt ({}); \
+                         choose another --experiment ID",
+                        id,
+                        name,
+                        path.display()
+                    )
+                    .into());
+                }
+            }
+            info!("Resuming experiment {}", id);
+            return Ok(experiment);
+        }
+
+        std::fs::create_dir_all(experiment.dir.join("prompts"))?;
+        std::fs::write(
+            experiment.dir.join("config.json"),
+            serde_json::to_string_pretty(config)?,
+        )?;
+        std::fs::write(experiment.prompt_path("system"), system_prompt)?;
+        for (style, template) in templates {
+            std::fs::write(experiment.prompt_path(style), template)?;
+        }
+        info!("Created experiment {} at {}", id, experiment.dir.display());
+        Ok(experiment)
+    }
+
+    /// Open an existing experiment directory.
+    pub fn load(dir: &Path) -> Result<Self, BoxError> {
+        let config_path = dir.join("config.json");
+        let config: ExperimentConfig = serde_json::from_str(
+            &std::fs::read_to_string(&config_path)
+                .map_err(|e| format!("Failed to read {}: {}", config_path.display(), e))?,
+        )?;
+        let id = dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| format!("Invalid experiment directory {}", dir.display()))?
+            .to_string();
+        Ok(Experiment {
+            id,
+            dir: dir.to_path_buf(),
+            config,
+        })
+    }
+
+    fn prompt_path(&self, name: &str) -> PathBuf {
+        self.dir.join("prompts").join(format!("{}.md", name))
+    }
+
+    pub fn result_path(&self, cell: &Cell) -> PathBuf {
+        self.dir
+            .join(&cell.task_id)
+            .join(&cell.style)
+            .join(format!("run_{}.rs", cell.run))
+    }
+
+    pub fn has_result(&self, cell: &Cell) -> bool {
+        self.result_path(cell).is_file()
+    }
+
+    pub fn save_result(&self, cell: &Cell, code: &str, meta: &RunMetadata) -> Result<(), BoxError> {
+        let code_path = self.result_path(cell);
+        if let Some(dir) = code_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        // Metadata first: a response file without metadata would look complete.
+        let meta_path = code_path.with_extension("meta.json");
+        write_atomic(&meta_path, serde_json::to_string_pretty(meta)?.as_bytes())?;
+        info!("Saved metadata to {}", meta_path.display());
+
+        write_atomic(&code_path, code.as_bytes())?;
+        info!("Saved code to {}", code_path.display());
+        Ok(())
+    }
+
+    /// Scan the experiment directory for saved responses.
+    pub fn completed_cells(&self) -> Result<Vec<Cell>, BoxError> {
+        let pattern = self.dir.join("*/*/run_*.rs").to_string_lossy().to_string();
+        let mut cells = Vec::new();
+        for entry in glob::glob(&pattern)? {
+            let path = entry?;
+            match cell_from_result_path(path.strip_prefix(&self.dir)?) {
+                Some(cell) => cells.push(cell),
+                None => warn!("Skipping unrecognised result file {}", path.display()),
+            }
+        }
+        cells.sort();
+        Ok(cells)
+    }
+
+    /// Rewrite `manifest.json` from the responses currently on disk.
+    pub fn write_manifest(
+        &self,
+        planned_cells: usize,
+        sweep_cells: usize,
+        status: RunStatus,
+    ) -> Result<(), BoxError> {
+        let path = self.dir.join("manifest.json");
+        let now = chrono_now();
+        let created_at = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<Manifest>(&s).ok())
+            .map_or_else(|| now.clone(), |m| m.created_at);
+        let manifest = Manifest {
+            experiment_id: self.id.clone(),
+            created_at,
+            updated_at: now,
+            status,
+            planned_cells,
+            sweep_cells,
+            completed_cells: self.completed_cells()?,
+        };
+        write_atomic(&path, serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+        Ok(())
+    }
+
+    /// The frozen `prompts/*.md`, by file name.
+    fn frozen_prompts(&self) -> Result<BTreeMap<String, Vec<u8>>, BoxError> {
+        let pattern = self.dir.join("prompts/*.md").to_string_lossy().to_string();
+        let mut prompts = BTreeMap::new();
+        for entry in glob::glob(&pattern)? {
+            let path = entry?;
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .ok_or_else(|| format!("Invalid prompt file {}", path.display()))?
+                .to_string();
+            prompts.insert(name, std::fs::read(&path)?);
+        }
+        Ok(prompts)
+    }
+
+    /// Fail unless `self` and `other` froze the same config and byte-identical
+    /// prompts, naming the first difference.
+    fn check_same_frozen(&self, other: &Experiment) -> Result<(), BoxError> {
+        if self.config != other.config {
+            return Err(format!(
+                "{} and {} were run with different configurations",
+                self.dir.display(),
+                other.dir.display()
+            )
+            .into());
+        }
+        let ours = self.frozen_prompts()?;
+        let theirs = other.frozen_prompts()?;
+        let names: BTreeSet<&String> = ours.keys().chain(theirs.keys()).collect();
+        for name in names {
+            if ours.get(name) != theirs.get(name) {
+                return Err(format!(
+                    "{} and {} were run with different prompts/{}",
+                    self.dir.display(),
+                    other.dir.display(),
+                    name
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy the frozen config and prompts of `self` into a fresh directory.
+    fn clone_frozen_into(&self, dir: &Path) -> Result<Experiment, BoxError> {
+        std::fs::create_dir_all(dir.join("prompts"))?;
+        std::fs::copy(self.dir.join("config.json"), dir.join("config.json"))?;
+        for entry in std::fs::read_dir(self.dir.join("prompts"))? {
+            let entry = entry?;
+            std::fs::copy(entry.path(), dir.join("prompts").join(entry.file_name()))?;
+        }
+        Self::load(dir)
+    }
+}
+
+/// Write via a temporary sibling and rename, so an interrupted write never
+/// leaves a truncated file behind.
+fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Parse `{task_id}/{style}/run_{N}.rs` relative to an experiment directory.
+fn cell_from_result_path(rel: &Path) -> Option<Cell> {
+    let parts: Vec<&str> = rel.iter().filter_map(|c| c.to_str()).collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    let run = parts[2]
+        .strip_prefix("run_")?
+        .strip_suffix(".rs")?
+        .parse()
+        .ok()?;
+    Some(Cell {
+        task_id: parts[0].to_string(),
+        style: parts[1].to_string(),
+        run,
+    })
+}
+
+// ── Shard merging ──────────────────────────────────────────────────────────
+
+/// Combine several shards of one experiment into the experiment directory
+/// `target`.
+///
+/// All shards (and an existing `target`) must share a configuration and
+/// byte-identical frozen prompts. A cell found in more than one
+/// shard is a duplicate when the responses are byte-identical and a conflict
+/// otherwise. Conflicts always abort the merge; missing cells abort it unless
+/// `allow_missing` is set. Nothing is written until all checks have passed.
+pub fn merge_shards(
+    shards: &[Experiment],
+    target: &Path,
+    expected: &[Cell],
+    allow_missing: bool,
+) -> Result<(), BoxError> {
+    let first = shards.first().ok_or("No shards to merge")?;
+    for shard in &shards[1..] {
+        first.check_same_frozen(shard)?;
+    }
+
+    let mut found: BTreeMap<Cell, Vec<PathBuf>> = BTreeMap::new();
+    for shard in shards {
+        let cells = shard.completed_cells()?;
+        info!("{}: {} cell(s)", shard.dir.display(), cells.len());
+        for cell in cells {
+            let path = shard.result_path(&cell);
+            found.entry(cell).or_default().push(path);
+        }
+    }
+
+    let mut conflicts = 0usize;
+    for (cell, paths) in &found {
+        if paths.len() < 2 {
+            continue;
+        }
+        let first = std::fs::read(&paths[0])?;
+        let mut identical = true;
+        for other in &paths[1..] {
+            if std::fs::read(other)? != first {
+                identical = false;
+            }
+        }
+        let sources: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+        if identical {
+            warn!(
+                "Duplicate cell {} (identical in {})",
+                cell,
+                sources.join(", ")
+            );
+        } else {
+            error!("Conflicting cell {}: {}", cell, sources.join(", "));
+            conflicts += 1;
+        }
+    }
+
+    let missing: Vec<&Cell> = expected
+        .iter()
+        .filter(|c| !found.contains_key(*c))
+        .collect();
+    for cell in &missing {
+        warn!("Missing cell {}", cell);
+    }
+
+    let expected_set: HashSet<&Cell> = expected.iter().collect();
+    let unexpected = found.keys().filter(|c| !expected_set.contains(c)).count();
+    if unexpected > 0 {
+        warn!(
+            "{} cell(s) are outside the experiment's configured sweep",
+            unexpected
+        );
+    }
+
+    if conflicts > 0 {
+        return Err(format!("{} conflicting cell(s); refusing to merge", conflicts).into());
+    }
+    if !missing.is_empty() && !allow_missing {
+        return Err(format!(
+            "{} of {} expected cell(s) missing; pass --allow-missing to merge anyway",
+            missing.len(),
+            expected.len()
+        )
+        .into());
+    }
+
+    let merged = if target.join("config.json").is_file() {
+        let existing = Experiment::load(target)?;
+        existing.check_same_frozen(first).map_err(|e| {
+            format!(
+                "{} already holds a different experiment: {}",
+                target.display(),
+                e
+            )
+        })?;
+        existing
+    } else {
+        first.clone_frozen_into(target)?
+    };
+
+    for (cell, paths) in &found {
+        let dst = merged.result_path(cell);
+        if dst.is_file() && std::fs::read(&dst)? != std::fs::read(&paths[0])? {
+            return Err(format!("{} already exists with different content", dst.display()).into());
+        }
+    }
+
+    for (cell, paths) in &found {
+        let src = &paths[0];
+        let dst = merged.result_path(cell);
+        // A shard directory may itself be the merge target.
+        if dst.is_file() {
+            continue;
+        }
+        if let Some(dir) = dst.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let meta_src = src.with_extension("meta.json");
+        if meta_src.is_file() {
+            std::fs::copy(&meta_src, dst.with_extension("meta.json"))?;
+        }
+        std::fs::copy(src, &dst)?;
+    }
+    merged.write_manifest(expected.len(), expected.len(), RunStatus::Merged)?;
+
+    info!(
+        "Merged {} cell(s) into {} ({} missing)",
+        found.len(),
+        merged.dir.display(),
+        missing.len()
+    );
+    Ok(())
+}
@@ -1,6 +1,15 @@
-use clap::Parser;
+mod adaptive;
+mod events;
+mod experiment;
+
+use adaptive::AdaptiveConfig;
+use clap::{Parser, Subcommand};
+use events::{EventLog, Pricing, Progress, Shutdown};
+use experiment::{Experiment, ExperimentConfig, RunStatus};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
 use tracing::{error, info, warn};
 
@@ -9,6 +18,9 @@ use tracing::{error, info, warn};
 #[derive(Parser)]
 #[command(name = "runner", about = "Experiment runner for LLM prompting style study")]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// OpenAI-compatible chat completions endpoint
     #[arg(long, default_value = "https://api.openai.com/v1/chat/completions")]
     api_url: String,
@@ -40,6 +52,92 @@ struct Cli {
     /// Sampling temperature (0.0 for reproducibility)
     #[arg(long, default_value_t = 0.0)]
     temperature: f64,
+
+    /// Run only shard I of N (1-based, e.g. "2/4"); every shard must use identical flags
+    #[arg(long, value_parser = parse_shard)]
+    shard: Option<Shard>,
+
+    /// Experiment ID to write under results/ (default: timestamp and model).
+    /// Reusing an ID resumes that experiment if its configuration is unchanged.
+    #[arg(long)]
+    experiment: Option<String>,
+
+    /// Keep sampling task×style groups whose pass-rate interval is wider than
+    /// --target-ci after --repetitions runs, up to --max-runs
+    #[arg(long)]
+    adaptive: bool,
+
+    /// Adaptive mode: maximum runs per task×style group
+    #[arg(long, default_value_t = 10)]
+    max_runs: u32,
+
+    /// Adaptive mode: target 95% Wilson interval half-width of a group's mean pass rate
+    #[arg(long, default_value_t = 0.1)]
+    target_ci: f64,
+
+    /// Price per million input tokens, for the spend estimate in the progress line
+    #[arg(long, default_value_t = 0.0)]
+    input_price: f64,
+
+    /// Price per million output tokens, for the spend estimate in the progress line
+    #[arg(long, default_value_t = 0.0)]
+    output_price: f64,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Merge shard experiment directories into one, checking for duplicate, conflicting and missing cells
+    Merge {
+        /// Experiment directories produced by the individual shards
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+
+        /// Experiment ID to merge into under results/ (default: ID of the first shard)
+        #[arg(long)]
+        into: Option<String>,
+
+        /// Copy the cells that are present even if some expected cells are missing
+        #[arg(long)]
+        allow_missing: bool,
+    },
+}
+
+/// One slice of a sweep: shard `index` (1-based) out of `count`.
+#[derive(Clone, Copy)]
+struct Shard {
+    index: usize,
+    count: usize,
+}
+
+impl Shard {
+    /// Whether the cell at `position` in the full cell list belongs to this shard.
+    fn contains(&self, position: usize) -> bool {
+        position % self.count == self.index - 1
+    }
+}
+
+impl std::fmt::Display for Shard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+fn parse_shard(s: &str) -> Result<Shard, String> {
+    let (index, count) = s
+        .split_once('/')
+        .ok_or_else(|| format!("expected I/N, got '{}'", s))?;
+    let index: usize = index
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid shard index '{}': {}", index, e))?;
+    let count: usize = count
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid shard count '{}': {}", count, e))?;
+    if count == 0 || index == 0 || index > count {
+        return Err(format!("shard index must be in 1..={}, got {}", count, index));
+    }
+    Ok(Shard { index, count })
 }
 
 // ── Task TOML schema ───────────────────────────────────────────────────────
@@ -47,6 +145,9 @@ struct Cli {
 #[derive(Deserialize)]
 struct TaskFile {
     task: Task,
+    /// SHA-256 of the raw TOML file, filled in by `load_tasks`.
+    #[serde(skip)]
+    definition_hash: String,
 }
 
 #[derive(Deserialize)]
@@ -60,7 +161,6 @@ struct Task {
     language: String,
     description: Description,
     constraints: Constraints,
-    #[allow(dead_code)]
     tests: Tests,
 }
 
@@ -81,16 +181,26 @@ struct Constraints {
 
 #[derive(Deserialize)]
 struct Tests {
-    #[allow(dead_code)]
     cases: Vec<TestCase>,
 }
 
 #[derive(Deserialize)]
 struct TestCase {
-    #[allow(dead_code)]
     name: String,
-    #[allow(dead_code)]
     code: String,
+    #[serde(default)]
+    set: TestSet,
+}
+
+/// Whether a prompt may show a test case. Eval reports pass rates on each
+/// set, so hard-coding the shown examples surfaces as a gap between them.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum TestSet {
+    /// Rendered by the `{{examples}}` placeholder.
+    Visible,
+    #[default]
+    Hidden,
 }
 
 // ── OpenAI-compatible API types ────────────────────────────────────────────
@@ -118,6 +228,8 @@ struct ChatResponse {
 #[derive(Deserialize)]
 struct Choice {
     message: MessageContent,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -136,6 +248,7 @@ struct Usage {
 
 #[derive(Serialize)]
 struct RunMetadata {
+    experiment_id: String,
     task_id: String,
     style: String,
     run: u32,
@@ -146,6 +259,63 @@ struct RunMetadata {
     input_tokens: u64,
     output_tokens: u64,
     total_tokens: u64,
+    /// Why generation stopped, as reported by the API (`length` means the
+    /// response was cut off at `max_tokens`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finish_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shard: Option<String>,
+    #[serde(flatten)]
+    provenance: Provenance,
+}
+
+/// SHA-256 hex digests of everything that went into a request, so eval can
+/// tell when a task or its tests changed after the response was generated.
+#[derive(Serialize)]
+struct Provenance {
+    /// Raw task TOML file
+    task_hash: String,
+    /// Test cases only, see `hash_test_cases`
+    tests_hash: String,
+    /// Style template as loaded
+    template_hash: String,
+    /// System prompt as sent
+    system_prompt_hash: String,
+    /// Rendered user prompt as sent
+    prompt_hash: String,
+}
+
+// ── Sweep cells ────────────────────────────────────────────────────────────
+
+/// A single task × style × run combination of the sweep.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+struct Cell {
+    task_id: String,
+    style: String,
+    run: u32,
+}
+
+impl std::fmt::Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} / {} / run {}", self.task_id, self.style, self.run)
+    }
+}
+
+/// Enumerate every cell of the sweep in a stable order (task, then style, then run).
+fn plan_cells(task_ids: &[String], styles: &[String], repetitions: u32) -> Vec<Cell> {
+    let mut cells = Vec::new();
+    for task_id in task_ids {
+        for style in styles {
+            for run in 1..=repetitions {
+                cells.push(Cell {
+                    task_id: task_id.clone(),
+                    style: style.clone(),
+                    run,
+                });
+            }
+        }
+    }
+    cells
 }
 
 // ── Constants ──────────────────────────────────────────────────────────────
@@ -164,6 +334,50 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let project_root = find_project_root()?;
     info!("Project root: {}", project_root.display());
 
+    if let Some(Command::Merge {
+        inputs,
+        into,
+        allow_missing,
+    }) = &cli.command
+    {
+        let shards = inputs
+            .iter()
+            .map(|dir| Experiment::load(dir))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Every shard was checked to share this config before merging.
+        let config = &shards[0].config;
+        let task_ids = if config.task_ids.is_empty() {
+            warn!("The shards don't record their task IDs; expecting the current definitions");
+            load_tasks(&project_root, config.tasks.as_deref())?
+                .into_iter()
+                .map(|t| t.task.id)
+                .collect()
+        } else {
+            config.task_ids.clone()
+        };
+        let expected = plan_cells(&task_ids, &config.styles, config.repetitions);
+        let id = into.clone().unwrap_or_else(|| shards[0].id.clone());
+        let target = project_root.join("results").join(id);
+        return experiment::merge_shards(&shards, &target, &expected, *allow_missing);
+    }
+
+    let styles = selected_styles(&cli);
+
+    let adaptive_config = if cli.adaptive {
+        if cli.shard.is_some() {
+            return Err("--adaptive cannot be combined with --shard".into());
+        }
+        if cli.max_runs < cli.repetitions {
+            return Err("--max-runs must be at least --repetitions".into());
+        }
+        Some(AdaptiveConfig {
+            max_runs: cli.max_runs,
+            target_ci: cli.target_ci,
+        })
+    } else {
+        None
+    };
+
     // Resolve API key (not needed for dry-run)
     let api_key = if cli.dry_run {
         String::new()
@@ -184,15 +398,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     })?;
     let system_prompt = system_prompt.trim().to_string();
 
-    // Determine styles
-    let styles: Vec<String> = match &cli.styles {
-        Some(s) => s.split(',').map(|v| v.trim().to_string()).collect(),
-        None => ALL_STYLES.iter().map(|s| s.to_string()).collect(),
-    };
-
     // Load style templates
-    let mut templates: std::collections::HashMap<String, String> =
-        std::collections::HashMap::new();
+    let mut templates: BTreeMap<String, String> = BTreeMap::new();
     for style in &styles {
         let tpl_path = project_root.join(format!("prompts/templates/{}.md", style));
         let tpl = std::fs::read_to_string(&tpl_path).map_err(|e| {
@@ -214,78 +421,304 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    let client = reqwest::Client::new();
+    // Visible cases are shown through `{{examples}}`; a template without it
+    // would silently turn them into hidden ones.
+    let with_visible = tasks
+        .iter()
+        .find(|t| t.task.tests.cases.iter().any(|tc| tc.set == TestSet::Visible));
+    if let Some(task) = with_visible {
+        if let Some(style) = templates.keys().find(|s| !templates[*s].contains("{{examples}}")) {
+            return Err(format!(
+                "Task {} has visible test cases, but template prompts/templates/{}.md \
+                 has no {{{{examples}}}} placeholder",
+                task.task.id, style
+            )
+            .into());
+        }
+    }
+
+    // Plan the sweep and keep only this shard's slice of it
+    let task_ids: Vec<String> = tasks.iter().map(|t| t.task.id.clone()).collect();
+    let all_cells = plan_cells(&task_ids, &styles, cli.repetitions);
+    let cells: Vec<Cell> = match cli.shard {
+        Some(shard) => all_cells
+            .iter()
+            .enumerate()
+            .filter(|(position, _)| shard.contains(*position))
+            .map(|(_, cell)| cell.clone())
+            .collect(),
+        None => all_cells.clone(),
+    };
+    if let Some(shard) = cli.shard {
+        info!(
+            "Shard {}: {} of {} cell(s)",
+            shard,
+            cells.len(),
+            all_cells.len()
+        );
+    }
 
-    for task_file in &tasks {
-        let task = &task_file.task;
-        let constraints_block = build_constraints_block(&task.constraints);
+    let tasks_by_id: HashMap<&str, &TaskFile> = tasks
+        .iter()
+        .map(|t| (t.task.id.as_str(), t))
+        .collect();
 
-        for style in &styles {
-            let template = &templates[style];
-            let rendered = render_template(template, &task.description.text, &constraints_block);
+    // Open the experiment namespace (dry runs leave no trace on disk)
+    let experiment = if cli.dry_run {
+        None
+    } else {
+        let config = ExperimentConfig {
+            api_url: cli.api_url.clone(),
+            model: cli.model.clone(),
+            repetitions: cli.repetitions,
+            tasks: cli.tasks.clone(),
+            task_ids: task_ids.clone(),
+            styles: styles.clone(),
+            max_tokens: cli.max_tokens,
+            temperature: cli.temperature,
+            adaptive: adaptive_config,
+        };
+        let id = cli
+            .experiment
+            .clone()
+            .unwrap_or_else(|| experiment::generate_id(&cli.model));
+        let experiment = Experiment::open_or_create(
+            &project_root.join("results"),
+            &id,
+            &config,
+            &system_prompt,
+            &templates,
+        )?;
+        experiment.write_manifest(cells.len(), all_cells.len(), RunStatus::Running)?;
+        Some(experiment)
+    };
 
-            for run in 1..=cli.repetitions {
-                info!(
-                    "Task={} Style={} Run={}/{}",
-                    task.id, style, run, cli.repetitions
+    let events = match &experiment {
+        Some(experiment) => EventLog::open(&experiment.dir.join("events.jsonl"))?,
+        None => EventLog::disabled(),
+    };
+    events.emit(
+        "run_started",
+        None,
+        serde_json::json!({
+            "cells": cells.len(),
+            "shard": cli.shard.map(|s| s.to_string()),
+        }),
+    );
+
+    let shutdown = Arc::new(Shutdown::default());
+    shutdown.install();
+
+    let mut sweep = Sweep {
+        cli: &cli,
+        api: ApiClient {
+            client: reqwest::Client::new(),
+            url: cli.api_url.clone(),
+            key: api_key,
+            model: cli.model.clone(),
+            max_tokens: cli.max_tokens,
+            temperature: cli.temperature,
+        },
+        experiment: experiment.as_ref(),
+        events: &events,
+        progress: Progress::new(
+            cells.len(),
+            Pricing {
+                input_per_mtok: cli.input_price,
+                output_per_mtok: cli.output_price,
+            },
+        ),
+        shutdown: &shutdown,
+        tasks_by_id,
+        templates: &templates,
+        system_prompt: &system_prompt,
+    };
+
+    let mut interrupted = !sweep.run_cells(&cells).await;
+
+    if let (Some(adaptive_config), Some(experiment), false) =
+        (adaptive_config, &experiment, interrupted)
+    {
+        let groups: Vec<(String, String)> = tasks
+            .iter()
+            .flat_map(|t| styles.iter().map(|s| (t.task.id.clone(), s.clone())))
+            .collect();
+        interrupted = !adaptive::run(
+            &mut sweep,
+            &project_root,
+            experiment,
+            adaptive_config,
+            cli.repetitions,
+            &groups,
+        )
+        .await?;
+    }
+    let progress = sweep.progress;
+    progress.finish();
+
+    if let Some(experiment) = &experiment {
+        let status = if interrupted {
+            RunStatus::Interrupted
+        } else {
+            RunStatus::Finished
+        };
+        experiment.write_manifest(cells.len(), all_cells.len(), status)?;
+        events.emit(
+            if interrupted { "interrupted" } else { "run_finished" },
+            None,
+            serde_json::json!({
+                "errors": progress.errors(),
+                "spend": progress.spend(),
+            }),
+        );
+        info!("Progress: {}", progress.line());
+        info!("Experiment ID: {}", experiment.id);
+    }
+
+    if interrupted {
+        warn!("Interrupted; rerun with the same --experiment ID to resume.");
+        std::process::exit(130);
+    }
+
+    info!("Done.");
+    Ok(())
+}
+
+// ── Sweep execution ────────────────────────────────────────────────────────
+
+/// Everything needed to request, save and log cells of one invocation.
+struct Sweep<'a> {
+    cli: &'a Cli,
+    api: ApiClient,
+    /// `None` for dry runs, which only print the rendered prompts.
+    experiment: Option<&'a Experiment>,
+    events: &'a EventLog,
+    progress: Progress,
+    shutdown: &'a Shutdown,
+    tasks_by_id: HashMap<&'a str, &'a TaskFile>,
+    templates: &'a BTreeMap<String, String>,
+    system_prompt: &'a str,
+}
+
+impl Sweep<'_> {
+    /// Request and save `cells` in order, skipping ones already on disk.
+    /// Returns `false` if the run was interrupted by a signal.
+    async fn run_cells(&mut self, cells: &[Cell]) -> bool {
+        for cell in cells {
+            if self.shutdown.requested() {
+                return false;
+            }
+
+            let task_file = self.tasks_by_id[cell.task_id.as_str()];
+            let task = &task_file.task;
+            let constraints_block = build_constraints_block(&task.constraints);
+            let examples_block = build_examples_block(&task.tests.cases);
+            let template = &self.templates[&cell.style];
+            let rendered = render_template(
+                template,
+                &task.description.text,
+                &constraints_block,
+                &examples_block,
+            );
+
+            let Some(experiment) = self.experiment else {
+                println!(
+                    "─── DRY RUN: {} / {} / run {} ───",
+                    cell.task_id, cell.style, cell.run
                 );
+                println!("[system]\n{}\n", self.system_prompt);
+                println!("[user]\n{}\n", rendered);
+                continue;
+            };
 
-                if cli.dry_run {
-                    println!("─── DRY RUN: {} / {} / run {} ───", task.id, style, run);
-                    println!("[system]\n{}\n", system_prompt);
-                    println!("[user]\n{}\n", rendered);
-                    continue;
-                }
+            if experiment.has_result(cell) {
+                info!("Skipping {}: already saved", cell);
+                self.progress.skipped();
+                continue;
+            }
+
+            info!("Task={} Style={} Run={}", cell.task_id, cell.style, cell.run);
+            self.events.emit("cell_started", Some(cell), serde_json::Value::Null);
 
-                let result = call_api_with_retry(
-                    &client,
-                    &cli.api_url,
-                    &api_key,
-                    &cli.model,
-                    &system_prompt,
+            let result = tokio::select! {
+                result = call_api_with_retry(
+                    &self.api,
+                    self.system_prompt,
                     &rendered,
-                    cli.max_tokens,
-                    cli.temperature,
-                )
-                .await;
-
-                match result {
-                    Ok((response, latency_ms)) => {
-                        let code = &response.choices[0].message.content;
-                        let usage = response.usage.as_ref();
-
-                        let meta = RunMetadata {
-                            task_id: task.id.clone(),
-                            style: style.clone(),
-                            run,
-                            model: cli.model.clone(),
-                            api_url: cli.api_url.clone(),
-                            timestamp: chrono_now(),
-                            latency_ms,
-                            input_tokens: usage.map_or(0, |u| u.prompt_tokens),
-                            output_tokens: usage.map_or(0, |u| u.completion_tokens),
-                            total_tokens: usage.map_or(0, |u| u.total_tokens),
-                        };
-
-                        if let Err(e) =
-                            save_results(&project_root, &task.id, style, run, code, &meta)
-                        {
+                    self.events,
+                    cell,
+                ) => result,
+                _ = self.shutdown.abandoned() => {
+                    self.events.emit("abandoned", Some(cell), serde_json::Value::Null);
+                    return false;
+                }
+            };
+
+            match result {
+                Ok((response, latency_ms)) => {
+                    let code = &response.choices[0].message.content;
+                    let usage = response.usage.as_ref();
+
+                    let meta = RunMetadata {
+                        experiment_id: experiment.id.clone(),
+                        task_id: cell.task_id.clone(),
+                        style: cell.style.clone(),
+                        run: cell.run,
+                        model: self.cli.model.clone(),
+                        api_url: self.cli.api_url.clone(),
+                        timestamp: chrono_now(),
+                        latency_ms,
+                        input_tokens: usage.map_or(0, |u| u.prompt_tokens),
+                        output_tokens: usage.map_or(0, |u| u.completion_tokens),
+                        total_tokens: usage.map_or(0, |u| u.total_tokens),
+                        finish_reason: response.choices[0].finish_reason.clone(),
+                        shard: self.cli.shard.map(|s| s.to_string()),
+                        provenance: Provenance {
+                            task_hash: task_file.definition_hash.clone(),
+                            tests_hash: hash_test_cases(&task.tests.cases),
+                            template_hash: sha256_hex(template.as_bytes()),
+                            system_prompt_hash: sha256_hex(self.system_prompt.as_bytes()),
+                            prompt_hash: sha256_hex(rendered.as_bytes()),
+                        },
+                    };
+
+                    match experiment.save_result(cell, code, &meta) {
+                        Ok(()) => {
+                            self.events.emit(
+                                "saved",
+                                Some(cell),
+                                serde_json::json!({
+                                    "latency_ms": meta.latency_ms,
+                                    "input_tokens": meta.input_tokens,
+                                    "output_tokens": meta.output_tokens,
+                                }),
+                            );
+                            self.progress.saved(meta.input_tokens, meta.output_tokens);
+                        }
+                        Err(e) => {
                             error!("Failed to save results: {}", e);
+                            self.events.emit(
+                                "failed",
+                                Some(cell),
+                                serde_json::json!({ "stage": "save", "error": e.to_string() }),
+                            );
+                            self.progress.failed();
                         }
                     }
-                    Err(e) => {
-                        error!(
-                            "API call failed for {} / {} / run {}: {}",
-                            task.id, style, run, e
-                        );
-                    }
+                }
+                Err(e) => {
+                    error!("API call failed for {}: {}", cell, e);
+                    self.events.emit(
+                        "failed",
+                        Some(cell),
+                        serde_json::json!({ "stage": "api", "error": e.to_string() }),
+                    );
+                    self.progress.failed();
                 }
             }
         }
+        true
     }
-
-    info!("Done.");
-    Ok(())
 }
 
 // ── Helpers ────────────────────────────────────────────────────────────────
@@ -310,6 +743,13 @@ fn find_project_root() -> Result<PathBuf, Box<dyn std::error::Error>> {
     Err("Could not find project root (directory containing tasks/ and prompts/)".into())
 }
 
+fn selected_styles(cli: &Cli) -> Vec<String> {
+    match &cli.styles {
+        Some(s) => s.split(',').map(|v| v.trim().to_string()).collect(),
+        None => ALL_STYLES.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
 fn load_tasks(
     root: &Path,
     filter: Option<&str>,
@@ -321,9 +761,10 @@ fn load_tasks(
     for entry in glob::glob(&pattern)? {
         let path = entry?;
         let content = std::fs::read_to_string(&path)?;
-        let task_file: TaskFile = toml::from_str(&content).map_err(|e| {
+        let mut task_file: TaskFile = toml::from_str(&content).map_err(|e| {
             format!("Failed to parse {}: {}", path.display(), e)
         })?;
+        task_file.definition_hash = sha256_hex(content.as_bytes());
 
         // Apply task ID glob filter
         if let Some(filt) = filter {
@@ -341,6 +782,34 @@ fn load_tasks(
     Ok(tasks)
 }
 
+fn sha256_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Hash the test cases independently of the rest of the task file, so that a
+/// description-only edit can be told apart from a change to the tests.
+/// Must stay in sync with `hash_test_cases` in eval.
+fn hash_test_cases(cases: &[TestCase]) -> String {
+    let mut buf = Vec::new();
+    for tc in cases {
+        buf.extend_from_slice(tc.name.as_bytes());
+        buf.push(0);
+        buf.extend_from_slice(tc.code.as_bytes());
+        buf.push(0);
+        // Only marked when visible, so hashes of tasks without the split
+        // stay as they were.
+        if tc.set == TestSet::Visible {
+            buf.extend_from_slice(b"visible");
+            buf.push(0);
+        }
+    }
+    sha256_hex(&buf)
+}
+
 fn build_constraints_block(constraints: &Constraints) -> String {
     let mut parts = Vec::new();
     if !constraints.must_use.is_empty() {
@@ -355,24 +824,60 @@ fn build_constraints_block(constraints: &Constraints) -> String {
     parts.join("\n")
 }
 
-fn render_template(template: &str, task_description: &str, constraints_block: &str) -> String {
+/// The task's visible test cases as `#[test]` functions in a Rust block,
+/// ending in a newline so that templates can put `{{examples}}` on a line of
+/// its own; empty without visible cases. Hidden cases are never rendered.
+fn build_examples_block(cases: &[TestCase]) -> String {
+    let tests: Vec<String> = cases
+        .iter()
+        .filter(|tc| tc.set == TestSet::Visible)
+        .map(|tc| {
+            let body: Vec<String> = tc
+                .code
+                .trim()
+                .lines()
+                .map(|l| if l.is_empty() { String::new() } else { format!("    {l}") })
+                .collect();
+            format!("#[test]\nfn {}() {{\n{}\n}}", tc.name, body.join("\n"))
+        })
+        .collect();
+    if tests.is_empty() {
+        return String::new();
+    }
+    format!("Your code must pass these tests:\n\n```rust\n{}\n```\n", tests.join("\n\n"))
+}
+
+fn render_template(
+    template: &str,
+    task_description: &str,
+    constraints_block: &str,
+    examples_block: &str,
+) -> String {
     template
         .replace("{{task_description}}", task_description)
         .replace("{{constraints_block}}", constraints_block)
+        .replace("{{examples}}", examples_block)
+}
+
+/// Endpoint and sampling settings shared by every request of a run.
+struct ApiClient {
+    client: reqwest::Client,
+    url: String,
+    key: String,
+    model: String,
+    max_tokens: u32,
+    temperature: f64,
 }
 
 async fn call_api_with_retry(
-    client: &reqwest::Client,
-    api_url: &str,
-    api_key: &str,
-    model: &str,
+    api: &ApiClient,
     system_prompt: &str,
     user_prompt: &str,
-    max_tokens: u32,
-    temperature: f64,
+    events: &EventLog,
+    cell: &Cell,
 ) -> Result<(ChatResponse, u128), Box<dyn std::error::Error>> {
     let request_body = ChatRequest {
-        model: model.to_string(),
+        model: api.model.clone(),
         messages: vec![
             ChatMessage {
                 role: "system".to_string(),
@@ -383,17 +888,19 @@ async fn call_api_with_retry(
                 content: user_prompt.to_string(),
             },
         ],
-        max_tokens,
-        temperature,
+        max_tokens: api.max_tokens,
+        temperature: api.temperature,
     };
 
     let body_json = serde_json::to_string(&request_body)?;
 
     for attempt in 0..MAX_RETRIES {
+        events.emit("attempt", Some(cell), serde_json::json!({ "attempt": attempt + 1 }));
         let start = Instant::now();
-        let resp = client
-            .post(api_url)
-            .header("Authorization", format!("Bearer {}", api_key))
+        let resp = api
+            .client
+            .post(&api.url)
+            .header("Authorization", format!("Bearer {}", api.key))
             .header("Content-Type", "application/json")
             .body(body_json.clone())
             .send()
@@ -420,6 +927,15 @@ async fn call_api_with_retry(
                 MAX_RETRIES,
                 delay
             );
+            events.emit(
+                "retry",
+                Some(cell),
+                serde_json::json!({
+                    "attempt": attempt + 1,
+                    "status": status.as_u16(),
+                    "delay_ms": delay,
+                }),
+            );
             tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
             continue;
         }
@@ -432,29 +948,6 @@ async fn call_api_with_retry(
     Err(format!("Exhausted {} retries", MAX_RETRIES).into())
 }
 
-fn save_results(
-    root: &Path,
-    task_id: &str,
-    style: &str,
-    run: u32,
-    code: &str,
-    meta: &RunMetadata,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let dir = root.join(format!("results/{}/{}", task_id, style));
-    std::fs::create_dir_all(&dir)?;
-
-    let code_path = dir.join(format!("run_{}.rs", run));
-    std::fs::write(&code_path, code)?;
-    info!("Saved code to {}", code_path.display());
-
-    let meta_path = dir.join(format!("run_{}.meta.json", run));
-    let meta_json = serde_json::to_string_pretty(meta)?;
-    std::fs::write(&meta_path, meta_json)?;
-    info!("Saved metadata to {}", meta_path.display());
-
-    Ok(())
-}
-
 fn chrono_now() -> String {
     // ISO 8601 UTC timestamp without external chrono dependency
     use std::time::SystemTime;
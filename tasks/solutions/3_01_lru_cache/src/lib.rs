@@ -1,10 +1,22 @@
 use std::collections::HashMap;
 
+const NIL: usize = usize::MAX;
+
+struct Entry {
+    key: i32,
+    value: i32,
+    prev: usize,
+    next: usize,
+}
+
+/// Entries live in a slab and form a doubly linked list by index, most
+/// recently used at the head, so every operation is O(1).
 pub struct LruCache {
     capacity: usize,
     map: HashMap<i32, usize>,
-    entries: Vec<(i32, i32)>,
-    order: Vec<i32>,
+    entries: Vec<Entry>,
+    head: usize,
+    tail: usize,
 }
 
 impl LruCache {
@@ -13,45 +25,74 @@ impl LruCache {
             capacity,
             map: HashMap::new(),
             entries: Vec::new(),
-            order: Vec::new(),
+            head: NIL,
+            tail: NIL,
         }
     }
 
     pub fn get(&mut self, key: &i32) -> Option<&i32> {
-        if self.map.contains_key(key) {
-            self.touch(*key);
-            let idx = self.map[key];
-            Some(&self.entries[idx].1)
+        let idx = *self.map.get(key)?;
+        self.touch(idx);
+        Some(&self.entries[idx].value)
+    }
+
+    pub fn put(&mut self, key: i32, value: i32) {
+        if let Some(&idx) = self.map.get(&key) {
+            self.entries[idx].value = value;
+            self.touch(idx);
+            return;
+        }
+        let idx = if self.map.len() >= self.capacity {
+            // Reuse the least recently used slot.
+            let idx = self.tail;
+            self.unlink(idx);
+            self.map.remove(&self.entries[idx].key);
+            self.entries[idx].key = key;
+            self.entries[idx].value = value;
+            idx
         } else {
-            None
+            self.entries.push(Entry {
+                key,
+                value,
+                prev: NIL,
+                next: NIL,
+            });
+            self.entries.len() - 1
+        };
+        self.map.insert(key, idx);
+        self.push_front(idx);
+    }
+
+    fn touch(&mut self, idx: usize) {
+        if self.head != idx {
+            self.unlink(idx);
+            self.push_front(idx);
         }
     }
 
-    pub fn put(&mut self, key: i32, value: i32) {
-        if self.map.contains_key(&key) {
-            let idx = self.map[&key];
-            self.entries[idx].1 = value;
-            self.touch(key);
+    fn unlink(&mut self, idx: usize) {
+        let (prev, next) = (self.entries[idx].prev, self.entries[idx].next);
+        if prev == NIL {
+            self.head = next;
         } else {
-            if self.map.len() >= self.capacity {
-                let lru_key = self.order.remove(0);
-                let idx = self.map.remove(&lru_key).unwrap();
-                self.entries[idx] = (key, value);
-                self.map.insert(key, idx);
-                self.order.push(key);
-            } else {
-                let idx = self.entries.len();
-                self.entries.push((key, value));
-                self.map.insert(key, idx);
-                self.order.push(key);
-            }
+            self.entries[prev].next = next;
+        }
+        if next == NIL {
+            self.tail = prev;
+        } else {
+            self.entries[next].prev = prev;
         }
     }
 
-    fn touch(&mut self, key: i32) {
-        if let Some(pos) = self.order.iter().position(|&k| k == key) {
-            self.order.remove(pos);
-            self.order.push(key);
+    fn push_front(&mut self, idx: usize) {
+        self.entries[idx].prev = NIL;
+        self.entries[idx].next = self.head;
+        if self.head != NIL {
+            self.entries[self.head].prev = idx;
+        }
+        self.head = idx;
+        if self.tail == NIL {
+            self.tail = idx;
         }
     }
 }